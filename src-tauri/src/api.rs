@@ -0,0 +1,120 @@
+use crate::config::get_nerevar_config;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+pub const DEFAULT_API_URL: &str = "https://nerevar.cc/api/v1";
+
+// Where the local stand-in API (e.g. a mock server during development) listens
+pub const LOCAL_API_URL: &str = "http://localhost:3001/api/v1";
+
+// Overrides the configured base URL; `local` selects LOCAL_API_URL
+pub const API_URL_ENV: &str = "NEREVAR_API_URL";
+
+/// The one HTTP client used for Nerevar API calls and release downloads.
+///
+/// Kept in Tauri state. The base URL is resolved on every request so changing
+/// it in the config takes effect without a restart.
+pub struct ApiClient {
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn new() -> Result<Self, String> {
+        // No overall timeout, downloads can legitimately take a long time
+        let http = reqwest::Client::builder()
+            .user_agent(format!("Nerevar/{}", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(Duration::from_secs(15))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        Ok(Self { http })
+    }
+
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    pub fn base_url(&self) -> String {
+        resolve_api_url()
+    }
+
+    /// GETs `path` (relative to the base URL, e.g. `/releases/tes3mp`) with the
+    /// URL-encoded `query` and parses the JSON body.
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, String> {
+        let api_url = self.base_url();
+        let url = format!("{}{}", api_url, path);
+
+        let mut request = self.http.get(&url);
+        if !query.is_empty() {
+            request = request.query(query);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to connect to API ({}): {}", api_url, e))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "API request to {} returned status: {}",
+                url,
+                response.status()
+            ));
+        }
+
+        response
+            .json::<T>()
+            .await
+            .map_err(|e| format!("Failed to parse API response from {}: {}", url, e))
+    }
+}
+
+/// Base URL of the Nerevar API: the `NEREVAR_API_URL` environment variable,
+/// then the local stand-in or custom URL from the Nerevar config, then nerevar.cc.
+pub fn resolve_api_url() -> String {
+    if let Ok(url) = std::env::var(API_URL_ENV) {
+        let url = url.trim();
+        if url.eq_ignore_ascii_case("local") {
+            return LOCAL_API_URL.to_string();
+        }
+        if !url.is_empty() {
+            return normalize_api_url(url);
+        }
+    }
+
+    match get_nerevar_config() {
+        Ok(Some(config)) if config.use_local_api => LOCAL_API_URL.to_string(),
+        Ok(Some(config)) => config
+            .api_url
+            .as_deref()
+            .map(normalize_api_url)
+            .unwrap_or_else(|| DEFAULT_API_URL.to_string()),
+        Ok(None) => DEFAULT_API_URL.to_string(),
+        Err(e) => {
+            log::warn!(
+                "Failed to read Nerevar config, using default API URL: {}",
+                e
+            );
+            DEFAULT_API_URL.to_string()
+        }
+    }
+}
+
+/// Checks that `url` is an absolute http(s) URL and strips any trailing slash.
+pub fn validate_api_url(url: &str) -> Result<String, String> {
+    let parsed =
+        reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid API URL '{}': {}", url, e))?;
+
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(format!("API URL must use http or https: {}", url));
+    }
+
+    Ok(normalize_api_url(url))
+}
+
+fn normalize_api_url(url: &str) -> String {
+    url.trim().trim_end_matches('/').to_string()
+}
@@ -0,0 +1,501 @@
+use crate::operations::check_cancelled;
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+use zip::ZipArchive;
+
+// Limits against decompression bombs. Real TES3MP releases unpack to about 3x
+// their archive size and stay well under a few GB.
+const MAX_EXTRACTED_SIZE: u64 = 16 * 1024 * 1024 * 1024;
+const MAX_TOTAL_RATIO: u64 = 200;
+const MAX_ENTRY_RATIO: u64 = 1000;
+// Tiny files can have huge ratios without being a problem
+const RATIO_CHECK_MIN_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Guesses the archive format from a file name or URL.
+    pub fn from_name(name: &str) -> Option<ArchiveKind> {
+        let name = name.to_lowercase();
+        // Ignore any query string on download URLs
+        let name = name.split('?').next().unwrap_or(&name);
+
+        if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else {
+            None
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveKind::Zip => "zip",
+            ArchiveKind::TarGz => "tar.gz",
+        }
+    }
+}
+
+/// Extracts the archive at `archive_path` into `extract_path`.
+///
+/// Used for every archive Nerevar unpacks, since they all come from URLs
+/// handed out by an API. Entries with absolute paths or `..` components,
+/// symlinks pointing outside `extract_path` and archives that decompress to
+/// absurd sizes are rejected, and the error names the offending entry.
+///
+/// Cancelling `cancel` stops extraction before the next entry.
+pub fn extract_archive(
+    archive_path: &Path,
+    kind: ArchiveKind,
+    extract_path: &Path,
+    cancel: &CancellationToken,
+) -> Result<(), String> {
+    let file =
+        File::open(archive_path).map_err(|e| format!("Failed to open archive file: {}", e))?;
+    let archive_size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read archive file: {}", e))?
+        .len();
+
+    fs::create_dir_all(extract_path)
+        .map_err(|e| format!("Failed to create extraction directory: {}", e))?;
+    let root = fs::canonicalize(extract_path)
+        .map_err(|e| format!("Failed to resolve extraction directory: {}", e))?;
+
+    let mut extractor = Extractor {
+        root,
+        cancel: cancel.clone(),
+        written: 0,
+        limit: archive_size
+            .max(1)
+            .saturating_mul(MAX_TOTAL_RATIO)
+            .min(MAX_EXTRACTED_SIZE),
+    };
+
+    match kind {
+        ArchiveKind::Zip => extract_zip(file, &mut extractor)
+            .map_err(|e| format!("Failed to extract zip file: {}", e))?,
+        ArchiveKind::TarGz => extract_tar_gz(file, &mut extractor)
+            .map_err(|e| format!("Failed to extract tar.gz file: {}", e))?,
+    }
+    extractor
+        .check_symlinks(&extractor.root)
+        .map_err(|e| format!("Failed to extract archive: {}", e))?;
+
+    log::info!(
+        "Extracted {} bytes to: {}",
+        extractor.written,
+        extract_path.display()
+    );
+    Ok(())
+}
+
+fn extract_zip(file: File, extractor: &mut Extractor) -> Result<(), String> {
+    let mut archive = ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read zip archive: {}", e))?;
+
+    for i in 0..archive.len() {
+        check_cancelled(&extractor.cancel)?;
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read zip entry {}: {}", i, e))?;
+        let name = entry.name().to_string();
+        let relative_path = sanitize_entry_name(&name)?;
+
+        let size = entry.size();
+        let compressed_size = entry.compressed_size().max(1);
+        if size > RATIO_CHECK_MIN_SIZE && size / compressed_size > MAX_ENTRY_RATIO {
+            return Err(reject(
+                &name,
+                format!(
+                    "compression ratio of {}:1 is too high",
+                    size / compressed_size
+                ),
+            ));
+        }
+
+        let mode = entry.unix_mode();
+        // Zips store symlinks as files holding the target, flagged by the unix mode
+        let is_symlink = mode.is_some_and(|m| m & 0o170000 == 0o120000);
+
+        if entry.is_dir() {
+            extractor.create_dir(&name, &relative_path)?;
+        } else if is_symlink {
+            let mut target = String::new();
+            entry
+                .by_ref()
+                .take(4096)
+                .read_to_string(&mut target)
+                .map_err(|e| reject(&name, format!("unreadable symlink target: {}", e)))?;
+            extractor.create_symlink(&name, &relative_path, &target)?;
+        } else {
+            extractor.write_file(&name, &relative_path, &mut entry, Some(size), mode)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn extract_tar_gz(file: File, extractor: &mut Extractor) -> Result<(), String> {
+    let mut archive = tar::Archive::new(GzDecoder::new(BufReader::new(file)));
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("Failed to read tar archive: {}", e))?;
+
+    for entry in entries {
+        check_cancelled(&extractor.cancel)?;
+        let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {}", e))?;
+        let name = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+        let relative_path = sanitize_entry_name(&name)?;
+        let link_name = entry
+            .link_name_bytes()
+            .map(|link| String::from_utf8_lossy(&link).to_string());
+        let mode = entry.header().mode().ok();
+
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => extractor.create_dir(&name, &relative_path)?,
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                extractor.write_file(&name, &relative_path, &mut entry, None, mode)?
+            }
+            tar::EntryType::Symlink => {
+                let target = link_name.ok_or(reject(&name, "symlink without a target"))?;
+                extractor.create_symlink(&name, &relative_path, &target)?;
+            }
+            tar::EntryType::Link => {
+                let target = link_name.ok_or(reject(&name, "hard link without a target"))?;
+                extractor.create_hard_link(&name, &relative_path, &target)?;
+            }
+            other => {
+                log::warn!("Skipping unsupported tar entry {} ({:?})", name, other);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn reject(name: &str, reason: impl std::fmt::Display) -> String {
+    format!("Refusing to extract archive entry '{}': {}", name, reason)
+}
+
+/// Turns an entry name into a relative path, rejecting absolute paths, drive
+/// prefixes and `..` components. Backslashes count as separators, since zips
+/// built on Windows sometimes use them.
+fn sanitize_entry_name(name: &str) -> Result<PathBuf, String> {
+    let normalized = name.replace('\\', "/");
+    if normalized.starts_with('/') {
+        return Err(reject(name, "absolute path"));
+    }
+
+    let mut path = PathBuf::new();
+    for part in normalized.split('/') {
+        match part {
+            "" | "." => continue,
+            ".." => return Err(reject(name, "path traversal (`..`)")),
+            // `C:` drive prefixes and NTFS alternate data streams
+            part if part.contains(':') => return Err(reject(name, "drive or stream prefix")),
+            part => path.push(part),
+        }
+    }
+
+    // An empty path (e.g. a `./` entry) is the root itself, which only
+    // directory entries can refer to
+    Ok(path)
+}
+
+/// Resolves a symlink target from the real folder the link is created in,
+/// following symlinks extracted earlier, and checks that it stays inside `root`.
+fn resolve_link_target(
+    name: &str,
+    root: &Path,
+    parent: &Path,
+    target: &str,
+) -> Result<PathBuf, String> {
+    let normalized = target.replace('\\', "/");
+    if normalized.starts_with('/') || normalized.contains(':') {
+        return Err(reject(
+            name,
+            format!("symlink to absolute path '{}'", target),
+        ));
+    }
+    let outside = || {
+        reject(
+            name,
+            format!("symlink '{}' points outside the target", target),
+        )
+    };
+
+    let mut resolved = parent.to_path_buf();
+    for part in normalized.split('/') {
+        match part {
+            "" | "." => continue,
+            ".." => {
+                if resolved == root {
+                    return Err(outside());
+                }
+                resolved.pop();
+            }
+            part => {
+                resolved.push(part);
+                // A `..` after a symlink climbs out of the folder it points to
+                let is_symlink = fs::symlink_metadata(&resolved)
+                    .is_ok_and(|metadata| metadata.file_type().is_symlink());
+                if is_symlink {
+                    resolved = fs::canonicalize(&resolved).map_err(|e| {
+                        reject(name, format!("unresolvable symlink in '{}': {}", target, e))
+                    })?;
+                }
+            }
+        }
+        if !resolved.starts_with(root) {
+            return Err(outside());
+        }
+    }
+
+    Ok(resolved)
+}
+
+struct Extractor {
+    // Canonical extraction directory
+    root: PathBuf,
+    cancel: CancellationToken,
+    written: u64,
+    limit: u64,
+}
+
+impl Extractor {
+    // Creates the folders of `relative_dir` one at a time, so a symlink extracted
+    // earlier can't be used to create them outside the root
+    fn ensure_dir(&self, name: &str, relative_dir: &Path) -> Result<PathBuf, String> {
+        let mut current = self.root.clone();
+
+        for component in relative_dir.components() {
+            current.push(component);
+            match fs::symlink_metadata(&current) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    let resolved = fs::canonicalize(&current)
+                        .map_err(|e| reject(name, format!("unresolvable symlink: {}", e)))?;
+                    if !resolved.starts_with(&self.root) || !resolved.is_dir() {
+                        return Err(reject(
+                            name,
+                            "path goes through a symlink that points outside the target",
+                        ));
+                    }
+                    current = resolved;
+                }
+                Ok(metadata) if metadata.is_dir() => {}
+                Ok(_) => {
+                    return Err(reject(
+                        name,
+                        format!("{} is not a directory", current.display()),
+                    ))
+                }
+                Err(_) => fs::create_dir(&current).map_err(|e| {
+                    format!("Failed to create directory {}: {}", current.display(), e)
+                })?,
+            }
+        }
+
+        Ok(current)
+    }
+
+    // Folder to place `relative_path` in, with any existing symlink at the
+    // destination removed so it is never written through
+    fn prepare_destination(&self, name: &str, relative_path: &Path) -> Result<PathBuf, String> {
+        let parent = self.ensure_dir(name, relative_path.parent().unwrap_or(Path::new("")))?;
+        let file_name = relative_path
+            .file_name()
+            .ok_or(reject(name, "missing file name"))?;
+        let destination = parent.join(file_name);
+
+        if let Ok(metadata) = fs::symlink_metadata(&destination) {
+            if metadata.file_type().is_symlink() {
+                fs::remove_file(&destination).map_err(|e| {
+                    format!("Failed to replace symlink {}: {}", destination.display(), e)
+                })?;
+            }
+        }
+        Ok(destination)
+    }
+
+    fn create_dir(&self, name: &str, relative_path: &Path) -> Result<(), String> {
+        self.ensure_dir(name, relative_path)?;
+        Ok(())
+    }
+
+    fn write_file(
+        &mut self,
+        name: &str,
+        relative_path: &Path,
+        reader: &mut dyn Read,
+        declared_size: Option<u64>,
+        mode: Option<u32>,
+    ) -> Result<(), String> {
+        let destination = self.prepare_destination(name, relative_path)?;
+        let mut file = File::create(&destination)
+            .map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+
+        // Never trust the sizes in the headers, stop one byte past the allowance
+        let remaining = self.limit.saturating_sub(self.written);
+        let allowance = declared_size.map_or(remaining, |size| size.min(remaining));
+        let copied = io::copy(&mut reader.take(allowance + 1), &mut file)
+            .map_err(|e| format!("Failed to write {}: {}", destination.display(), e))?;
+
+        if copied > allowance {
+            drop(file);
+            let _ = fs::remove_file(&destination);
+            return Err(match declared_size {
+                Some(size) if copied > size && size < remaining => {
+                    reject(name, "larger than its header claims")
+                }
+                _ => reject(
+                    name,
+                    format!(
+                        "archive expands beyond the {} byte extraction limit",
+                        self.limit
+                    ),
+                ),
+            });
+        }
+        self.written += copied;
+
+        set_mode(&destination, mode)
+    }
+
+    fn create_symlink(&self, name: &str, relative_path: &Path, target: &str) -> Result<(), String> {
+        let destination = self.prepare_destination(name, relative_path)?;
+        // The real folder, which differs from the entry name's when the link
+        // is placed through an earlier symlink
+        let parent = destination.parent().unwrap_or(&self.root);
+        resolve_link_target(name, &self.root, parent, target)?;
+        make_symlink(target, &destination)
+    }
+
+    // Symlinks are checked as they are extracted, but a later entry can still
+    // change where an earlier one leads, so the finished tree is checked again
+    fn check_symlinks(&self, dir: &Path) -> Result<(), String> {
+        let entries = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?
+                .path();
+            let metadata = fs::symlink_metadata(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+            if metadata.file_type().is_symlink() {
+                match fs::canonicalize(&path) {
+                    Ok(resolved) if resolved.starts_with(&self.root) => {}
+                    Ok(resolved) => {
+                        return Err(format!(
+                            "Refusing extracted symlink {}: it points outside the target ({})",
+                            path.display(),
+                            resolved.display()
+                        ))
+                    }
+                    // Dangling, so it could only ever point somewhere unchecked
+                    Err(_) => {
+                        log::warn!("Removing dangling symlink {}", path.display());
+                        fs::remove_file(&path).map_err(|e| {
+                            format!("Failed to remove symlink {}: {}", path.display(), e)
+                        })?;
+                    }
+                }
+            } else if metadata.is_dir() {
+                self.check_symlinks(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Hard link targets are relative to the archive root
+    fn create_hard_link(
+        &mut self,
+        name: &str,
+        relative_path: &Path,
+        target: &str,
+    ) -> Result<(), String> {
+        let target_path = sanitize_entry_name(target).map_err(|_| {
+            reject(
+                name,
+                format!("hard link to '{}' outside the target", target),
+            )
+        })?;
+        let source = self.ensure_dir(name, target_path.parent().unwrap_or(Path::new("")))?;
+        let source = source.join(target_path.file_name().unwrap_or_default());
+        if !source.is_file() {
+            return Err(reject(
+                name,
+                format!("hard link to missing file '{}'", target),
+            ));
+        }
+        // The file itself may be a symlink extracted earlier
+        let source = fs::canonicalize(&source)
+            .ok()
+            .filter(|source| source.starts_with(&self.root))
+            .ok_or(reject(
+                name,
+                format!("hard link to '{}' outside the target", target),
+            ))?;
+
+        // Copy rather than link, the extracted tree gets moved around afterwards
+        let mut reader = File::open(&source)
+            .map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+        let mode = source
+            .metadata()
+            .ok()
+            .and_then(|metadata| file_mode(&metadata));
+        self.write_file(name, relative_path, &mut reader, None, mode)
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+// Keeps the permission bits from the archive, e.g. so Linux binaries stay executable
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Some(mode) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))
+            .map_err(|e| format!("Failed to set permissions on {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: Option<u32>) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_symlink(target: &str, destination: &Path) -> Result<(), String> {
+    std::os::unix::fs::symlink(target, destination)
+        .map_err(|e| format!("Failed to create symlink {}: {}", destination.display(), e))
+}
+
+// Creating symlinks needs extra privileges on Windows, and Windows releases don't use them
+#[cfg(not(unix))]
+fn make_symlink(target: &str, destination: &Path) -> Result<(), String> {
+    log::warn!("Skipping symlink {} -> {}", destination.display(), target);
+    Ok(())
+}
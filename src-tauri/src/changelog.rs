@@ -0,0 +1,152 @@
+use crate::api::ApiClient;
+use crate::types::{Channel, ReleaseInfo, ReleaseNotes, UpdateCheckResponse, VersionComparison};
+use crate::utils::get_appdata_dir;
+use crate::version_compare::{compare_versions, parse_version};
+use std::fs;
+use std::path::PathBuf;
+
+// Changelogs already fetched, one file per product, under the app data dir
+const CHANGELOGS_DIR: &str = "changelogs";
+
+fn cache_path(product: &str) -> Result<PathBuf, String> {
+    Ok(get_appdata_dir()?
+        .join(CHANGELOGS_DIR)
+        .join(format!("{}.json", product)))
+}
+
+fn read_cache(product: &str) -> Result<Vec<ReleaseNotes>, String> {
+    let path = cache_path(product)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read changelog cache: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse changelog cache: {}", e))
+}
+
+fn write_cache(product: &str, entries: &[ReleaseNotes]) -> Result<(), String> {
+    let path = cache_path(product)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create changelog cache directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize changelog: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write changelog cache: {}", e))
+}
+
+// Adds `fetched` to `cached`, replacing entries for the same version since
+// notes can be edited after a release
+fn merge(cached: &mut Vec<ReleaseNotes>, fetched: Vec<ReleaseNotes>) {
+    for entry in fetched {
+        cached.retain(|c| c.version != entry.version);
+        cached.push(entry);
+    }
+}
+
+/// Notes for every release of `product` (`tes3mp` or `nerevar`) newer than
+/// `installed` up to and including `latest`, newest first.
+///
+/// Fetched notes are added to a cache in the app data directory, which is used
+/// on its own when the API can't be reached. Never fails: a missing changelog
+/// shouldn't stop an update check, so errors are logged and whatever is cached
+/// is returned.
+pub async fn changelog_between(
+    api: &ApiClient,
+    product: &str,
+    channel: Channel,
+    installed: &str,
+    latest: &str,
+) -> Vec<ReleaseNotes> {
+    let (Some(from), Some(to)) = (parse_version(installed), parse_version(latest)) else {
+        log::warn!(
+            "Can't list {} changes between {} and {}",
+            product,
+            installed,
+            latest
+        );
+        return Vec::new();
+    };
+    if to.cmp_precedence(&from).is_le() {
+        return Vec::new();
+    }
+
+    let mut cached = read_cache(product).unwrap_or_else(|e| {
+        log::warn!("Ignoring {} changelog cache: {}", product, e);
+        Vec::new()
+    });
+
+    let fetched = api
+        .get_json::<Vec<ReleaseNotes>>(
+            &format!("/releases/{}/changelog", product),
+            &[
+                ("channel", channel.as_str()),
+                ("from", installed),
+                ("to", latest),
+            ],
+        )
+        .await;
+    match fetched {
+        Ok(fetched) => {
+            log::info!(
+                "Fetched {} {} changelog entries ({} to {})",
+                fetched.len(),
+                product,
+                installed,
+                latest
+            );
+            merge(&mut cached, fetched);
+            if let Err(e) = write_cache(product, &cached) {
+                log::warn!("Failed to cache {} changelog: {}", product, e);
+            }
+        }
+        Err(e) => log::warn!("Failed to fetch {} changelog, using cache: {}", product, e),
+    }
+
+    let mut entries: Vec<(semver::Version, ReleaseNotes)> = cached
+        .into_iter()
+        .filter_map(|entry| Some((parse_version(&entry.version)?, entry)))
+        .filter(|(version, _)| {
+            version.cmp_precedence(&from).is_gt() && version.cmp_precedence(&to).is_le()
+        })
+        .collect();
+    entries.sort_by(|(a, _), (b, _)| b.cmp_precedence(a));
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Fills in the notes, date, breaking flag and changelog of an update check
+/// for `release`, from the release itself and the changelog since the
+/// installed version.
+pub async fn add_release_notes(
+    api: &ApiClient,
+    product: &str,
+    channel: Channel,
+    release: &ReleaseInfo,
+    response: &mut UpdateCheckResponse,
+) {
+    let changelog = changelog_between(
+        api,
+        product,
+        channel,
+        &response.current_version,
+        &release.version,
+    )
+    .await;
+    let latest = changelog.iter().find(|entry| {
+        compare_versions(&entry.version, &release.version) == VersionComparison::Equal
+    });
+
+    response.release_notes = release
+        .notes
+        .clone()
+        .or_else(|| latest.map(|entry| entry.notes.clone()));
+    response.release_date = release
+        .release_date
+        .clone()
+        .or_else(|| latest.and_then(|entry| entry.release_date.clone()));
+    response.breaking = changelog.iter().any(|entry| entry.breaking)
+        || (response.comparison == VersionComparison::Newer && release.breaking);
+    response.changelog = changelog;
+}
@@ -1,257 +1,186 @@
-use crate::types::{Mode, NerevarConfig, OpenMWConfig, UpdateCheckResponse};
-use crate::utils::{find_tes3mp_folder, get_appdata_dir};
+use crate::api::ApiClient;
+use crate::changelog::add_release_notes;
+use crate::download::download_to_file;
+use crate::integrity::verify_app_update;
+use crate::operations::{OperationRegistry, CANCELLED};
+use crate::process_stats::ProcessStatsHistory;
+use crate::scheduler::Scheduler;
+use crate::server::ServerWatchdog;
+use crate::server_log::ServerLog;
+use crate::supervisor::ProcessSupervisor;
+use crate::types::{
+    Channel, InstallError, InstalledVersion, IntegrityReport, Mode, NerevarConfig, OpenMWConfig,
+    Platform, ProcessInfo, ProcessRole, ProcessStats, ReleaseAsset, ReleaseInfo, RepairReport,
+    ResetReport, ScheduleStatus, ScheduledRun, ScheduledTask, ServerLogLine, UpdateCheckResponse,
+    UpgradeReport, VersionComparison, WatchdogPolicy, WatchdogStatus,
+};
+use crate::updater::{AppUpdate, AppUpdates, APP_UPDATE_PROGRESS_EVENT};
+use crate::utils::get_appdata_dir;
+use crate::version_compare::compare_versions;
 use open;
-use std::fs::{self, File};
-use std::io::BufReader;
-use std::net::TcpStream;
+use std::fs;
 use std::path::Path;
-use std::process::{Command, Stdio};
 use std::time::Instant;
-use tauri::Emitter;
-use zip::ZipArchive;
+use tauri::Manager;
 
 #[tauri::command]
-pub async fn download_latest_windows_release() -> Result<String, String> {
-    // Use temp directory to avoid Tauri rebuilds
-    let temp_dir = std::env::temp_dir();
-
-    let zip_filename = "tes3mp_latest.zip";
-    let extract_dir = "tes3mp_extracted";
-
-    let zip_path = temp_dir.join(zip_filename);
-    let extract_path = temp_dir.join(extract_dir);
-
-    log::info!("Using temp directory: {}", temp_dir.display());
-    log::info!("Zip will be saved to: {}", zip_path.display());
-    log::info!("Will extract to: {}", extract_path.display());
-
-    //Step 0: get latest release url from nerevar-api which returns a body json { url: string, version: string }
-    let api_url = "https://nerevar.cc/api/v1";
-    let version = match reqwest::get(format!("{}/releases/tes3mp", api_url)).await {
-        Ok(response) => match response.json::<serde_json::Value>().await {
-            Ok(url_response) => match url_response["version"].as_str() {
-                Some(version) => {
-                    log::info!("Successfully retrieved version from API: {}", version);
-                    version.to_string()
-                }
-                None => {
-                    log::warn!("API returned invalid response format, using fallback version");
-                    "".to_string()
-                }
-            },
-            Err(e) => {
-                log::warn!(
-                    "Failed to parse API response: {}, using fallback version",
-                    e
-                );
-                "".to_string()
-            }
-        },
-        Err(e) => {
-            log::warn!(
-                "Failed to connect to API ({}): {}, using fallback version",
-                api_url,
-                e
-            );
-            "".to_string()
-        }
-    };
-    let url = match reqwest::get(format!("{}/releases/tes3mp", api_url)).await {
-        Ok(response) => match response.json::<serde_json::Value>().await {
-            Ok(url_response) => match url_response["url"].as_str() {
-                Some(url) => {
-                    log::info!("Successfully retrieved URL from API: {}", url);
-                    url.to_string()
-                }
-                None => {
-                    log::warn!("API returned invalid response format, using fallback URL");
-                    "".to_string()
-                }
-            },
-            Err(e) => {
-                log::warn!("Failed to parse API response: {}, using fallback URL", e);
-                "".to_string()
-            }
-        },
-        Err(e) => {
-            log::warn!(
-                "Failed to connect to API ({}): {}, using fallback URL",
-                api_url,
-                e
-            );
-            "".to_string()
-        }
-    };
-    log::info!("Latest release url: {}", url);
-
-    // Step 1: Download the zip file
-    log::info!("Downloading TES3MP from: {}", url);
-    let response = reqwest::get(url.as_str())
-        .await
-        .map_err(|e| format!("Failed to download file: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "Download failed with status: {}",
-            response.status()
-        ));
-    }
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Failed to read response bytes: {}", e))?;
-
-    // Step 2: Save the zip file
-    fs::write(&zip_path, bytes).map_err(|e| format!("Failed to save zip file: {}", e))?;
-
-    log::info!("Zip file saved as: {}", zip_path.display());
-
-    // Step 3: Extract the zip file
-    let zip_file = File::open(&zip_path).map_err(|e| format!("Failed to open zip file: {}", e))?;
-
-    let mut archive = ZipArchive::new(BufReader::new(zip_file))
-        .map_err(|e| format!("Failed to read zip archive: {}", e))?;
-
-    // Create extraction directory
-    if extract_path.exists() {
-        log::info!(
-            "Removing existing extraction directory: {}",
-            extract_path.display()
-        );
-        fs::remove_dir_all(&extract_path)
-            .map_err(|e| format!("Failed to remove existing extraction directory: {}", e))?;
-    }
+pub async fn download_latest_windows_release(
+    app_handle: tauri::AppHandle,
+    operations: tauri::State<'_, OperationRegistry>,
+    operation_id: Option<String>,
+) -> Result<String, InstallError> {
+    let operation = operations.start(&app_handle, "install", operation_id)?;
+    operation.finish(crate::installer::install_latest_release(&app_handle, operation.token()).await)
+}
 
-    log::info!("Extracting zip file to: {}", extract_path.display());
-    archive
-        .extract(&extract_path)
-        .map_err(|e| format!("Failed to extract zip file: {}", e))?;
+#[tauri::command]
+pub async fn upgrade_tes3mp(
+    app_handle: tauri::AppHandle,
+    operations: tauri::State<'_, OperationRegistry>,
+    allow_downgrade: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<UpgradeReport, InstallError> {
+    let operation = operations.start(&app_handle, "upgrade", operation_id)?;
+    operation.finish(
+        crate::installer::upgrade_latest_release(
+            &app_handle,
+            allow_downgrade.unwrap_or(false),
+            operation.token(),
+        )
+        .await,
+    )
+}
 
-    log::info!("Zip file extracted to: {}", extract_path.display());
+#[tauri::command]
+pub async fn install_tes3mp_from_archive(
+    app_handle: tauri::AppHandle,
+    operations: tauri::State<'_, OperationRegistry>,
+    archive_path: String,
+    version: Option<String>,
+    preserve_data: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<UpgradeReport, InstallError> {
+    let operation = operations.start(&app_handle, "install", operation_id)?;
+    operation.finish(crate::installer::install_local_archive(
+        Path::new(&archive_path),
+        version,
+        preserve_data.unwrap_or(true),
+        operation.token(),
+    ))
+}
 
-    // Step 4: Delete the zip file
-    fs::remove_file(&zip_path).map_err(|e| format!("Failed to delete zip file: {}", e))?;
+#[tauri::command]
+pub async fn verify_tes3mp_install(
+    app_handle: tauri::AppHandle,
+    version: Option<String>,
+) -> Result<IntegrityReport, String> {
+    crate::manifest::verify_installation(&app_handle, version).await
+}
 
-    log::info!("Zip file deleted: {}", zip_path.display());
+#[tauri::command]
+pub async fn repair_tes3mp_install(
+    app_handle: tauri::AppHandle,
+    operations: tauri::State<'_, OperationRegistry>,
+    version: Option<String>,
+    archive_path: Option<String>,
+    operation_id: Option<String>,
+) -> Result<RepairReport, InstallError> {
+    let operation = operations.start(&app_handle, "repair", operation_id)?;
+    operation.finish(
+        crate::manifest::repair_installation(&app_handle, version, archive_path, operation.token())
+            .await,
+    )
+}
 
-    // Step 5: Check if tes3mp.exe is directly in the extracted folder
-    let exe_path = extract_path.join("tes3mp.exe");
+#[tauri::command]
+pub async fn factory_reset_nerevar(backup: bool) -> Result<ResetReport, String> {
+    // Zipping and deleting whole installs would freeze the window on the main thread
+    tokio::task::spawn_blocking(move || crate::reset::reset_nerevar(backup))
+        .await
+        .map_err(|e| format!("Factory reset task failed: {}", e))?
+}
 
-    log::info!(
-        "Checking for tes3mp.exe directly in: {}",
-        extract_path.display()
-    );
+#[tauri::command]
+pub async fn factory_reset_tes3mp(
+    app_handle: tauri::AppHandle,
+    operations: tauri::State<'_, OperationRegistry>,
+    backup: bool,
+    archive_path: Option<String>,
+    operation_id: Option<String>,
+) -> Result<ResetReport, InstallError> {
+    let operation = operations.start(&app_handle, "reset", operation_id)?;
+    operation.finish(
+        crate::reset::reset_tes3mp(&app_handle, backup, archive_path, operation.token()).await,
+    )
+}
 
-    let (tes3mp_folder, final_exe_path) = if exe_path.exists() {
-        log::info!(
-            "Found tes3mp.exe directly in extraction folder: {}",
-            exe_path.display()
-        );
-        log::info!(
-            "Using extraction folder as TES3MP folder: {}",
-            extract_path.display()
-        );
-        (extract_path.clone(), exe_path)
+#[tauri::command]
+pub fn cancel_operation(
+    operations: tauri::State<'_, OperationRegistry>,
+    operation_id: String,
+) -> Result<bool, String> {
+    if operations.cancel(&operation_id) {
+        Ok(true)
     } else {
-        // Step 6: Look for TES3MP folder (should be named something like tes3mp.Win64.release.0.8.1)
-        log::info!(
-            "tes3mp.exe not found directly, looking for TES3MP folder in: {}",
-            extract_path.display()
-        );
-        let tes3mp_folder = find_tes3mp_folder(&extract_path)?;
-        log::info!("Found TES3MP folder: {}", tes3mp_folder.display());
-        let exe_path = tes3mp_folder.join("tes3mp.exe");
-        (tes3mp_folder, exe_path)
-    };
-
-    log::info!("Checking for tes3mp.exe at: {}", final_exe_path.display());
-    if !final_exe_path.exists() {
-        // List all files in the folder for debugging
-        log::error!("tes3mp.exe not found in: {}", tes3mp_folder.display());
-        if let Ok(entries) = fs::read_dir(&tes3mp_folder) {
-            log::info!("Files in TES3MP folder:");
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    log::info!("  - {}", entry.file_name().to_string_lossy());
-                }
-            }
-        }
-        return Err(format!(
-            "tes3mp.exe not found in: {}",
-            tes3mp_folder.display()
-        ));
+        Err(format!("No running operation with ID: {}", operation_id))
     }
+}
 
-    log::info!("Found tes3mp.exe at: {}", final_exe_path.display());
-    log::info!(
-        "TES3MP installation verified in: {}",
-        tes3mp_folder.display()
-    );
-
-    // Step 7: Move TES3MP to permanent AppData location
-    let appdata_dir = get_appdata_dir()?;
-    let permanent_tes3mp_path = appdata_dir.join("TES3MP");
-
-    log::info!(
-        "Moving TES3MP to permanent location: {}",
-        permanent_tes3mp_path.display()
-    );
+#[tauri::command]
+pub fn list_tes3mp_versions() -> Result<Vec<InstalledVersion>, String> {
+    crate::versions::list_installed_versions()
+}
 
-    // Remove existing TES3MP folder if it exists
-    if permanent_tes3mp_path.exists() {
-        log::info!(
-            "Removing existing TES3MP installation: {}",
-            permanent_tes3mp_path.display()
-        );
-        fs::remove_dir_all(&permanent_tes3mp_path)
-            .map_err(|e| format!("Failed to remove existing TES3MP installation: {}", e))?;
-    }
+#[tauri::command]
+pub fn activate_tes3mp_version(version: String) -> Result<NerevarConfig, String> {
+    crate::versions::activate_version(&version)
+}
 
-    // Move the extracted folder to AppData
-    fs::rename(&tes3mp_folder, &permanent_tes3mp_path)
-        .map_err(|e| format!("Failed to move TES3MP to AppData: {}", e))?;
+#[tauri::command]
+pub fn rollback_tes3mp_version() -> Result<NerevarConfig, String> {
+    crate::versions::rollback_version()
+}
 
-    log::info!(
-        "TES3MP successfully moved to: {}",
-        permanent_tes3mp_path.display()
-    );
+#[tauri::command]
+pub fn pin_tes3mp_version(server: String, version: String) -> Result<NerevarConfig, String> {
+    crate::versions::pin_version(&server, Some(&version))
+}
 
-    // Step 8: Clean up temp extraction directory
-    if extract_path.exists() {
-        log::info!(
-            "Cleaning up temp extraction directory: {}",
-            extract_path.display()
-        );
-        fs::remove_dir_all(&extract_path)
-            .map_err(|e| format!("Failed to clean up temp extraction directory: {}", e))?;
-    }
+#[tauri::command]
+pub fn unpin_tes3mp_version(server: String) -> Result<NerevarConfig, String> {
+    crate::versions::pin_version(&server, None)
+}
 
-    // Step 9: Create/update Nerevar config
-    let config_path = appdata_dir.join("config.json");
-    let config = NerevarConfig {
-        tes3mp_path: permanent_tes3mp_path.to_string_lossy().to_string(),
-        version: version,
-        last_updated: chrono::Utc::now().to_rfc3339(),
-        mode: Some(Mode::Player),
-    };
+#[tauri::command]
+pub async fn remove_tes3mp_version(version: String) -> Result<bool, String> {
+    // Deleting a whole install would freeze the window on the main thread
+    tokio::task::spawn_blocking(move || crate::versions::remove_version(&version))
+        .await
+        .map_err(|e| format!("Remove version task failed: {}", e))??;
+    Ok(true)
+}
 
-    let config_json = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize config: {}", e))?;
-    fs::write(&config_path, config_json)
-        .map_err(|e| format!("Failed to write config file: {}", e))?;
+#[tauri::command]
+pub fn get_nerevar_config() -> Result<Option<NerevarConfig>, String> {
+    crate::config::get_nerevar_config()
+}
 
-    log::info!("Nerevar config updated: {}", config_path.display());
+#[tauri::command]
+pub fn get_api_url() -> String {
+    crate::api::resolve_api_url()
+}
 
-    Ok(format!(
-        "TES3MP successfully installed to: {}",
-        permanent_tes3mp_path.display()
-    ))
+#[tauri::command]
+pub fn set_api_settings(
+    api_url: Option<String>,
+    use_local_api: bool,
+) -> Result<NerevarConfig, String> {
+    crate::config::set_api_settings(api_url, use_local_api)
 }
 
 #[tauri::command]
-pub fn get_nerevar_config() -> Result<Option<NerevarConfig>, String> {
-    crate::config::get_nerevar_config()
+pub fn set_release_channel(channel: Channel) -> Result<NerevarConfig, String> {
+    crate::config::set_release_channel(channel)
 }
 
 #[tauri::command]
@@ -272,18 +201,49 @@ pub fn get_app_version() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn ping_server_tcp(ip: String, port: u16) -> Option<u128> {
+pub async fn ping_server_tcp(
+    app_handle: tauri::AppHandle,
+    ip: String,
+    port: u16,
+    operation_id: Option<String>,
+) -> Option<u128> {
+    // Pings are only registered when the caller wants to be able to cancel them
+    let operation = match operation_id {
+        Some(id) => Some(
+            app_handle
+                .state::<OperationRegistry>()
+                .start(&app_handle, "ping", Some(id))
+                .ok()?,
+        ),
+        None => None,
+    };
+
     let addr = format!("{}:{}", ip, port);
     let start = Instant::now();
-    match TcpStream::connect_timeout(&addr.parse().unwrap(), std::time::Duration::from_secs(2)) {
-        Ok(_) => Some(start.elapsed().as_millis()),
-        Err(_) => None,
+    let connect = tokio::time::timeout(
+        std::time::Duration::from_secs(2),
+        tokio::net::TcpStream::connect(addr),
+    );
+    let connected = match &operation {
+        Some(operation) => tokio::select! {
+            _ = operation.token().cancelled() => return None,
+            result = connect => result,
+        },
+        None => connect.await,
+    };
+
+    match connected {
+        Ok(Ok(_)) => Some(start.elapsed().as_millis()),
+        _ => None,
     }
 }
 
 // Placeholder for other commands - will be added in next steps
 #[tauri::command]
-pub async fn run_openmw_wizard(app_handle: tauri::AppHandle) -> Result<String, String> {
+pub async fn run_openmw_wizard(
+    app_handle: tauri::AppHandle,
+    supervisor: tauri::State<'_, ProcessSupervisor>,
+) -> Result<String, String> {
     log::info!("Running OpenMW wizard");
 
     // Get the nerevar config to find the TES3MP installation path
@@ -294,7 +254,8 @@ pub async fn run_openmw_wizard(app_handle: tauri::AppHandle) -> Result<String, S
     let tes3mp_path = config.tes3mp_path;
 
     // Construct the path to the OpenMW wizard executable
-    let openmw_wizard_path = Path::new(&tes3mp_path).join("openmw-wizard.exe");
+    let openmw_wizard_path =
+        Path::new(&tes3mp_path).join(Platform::current().exe_name("openmw-wizard"));
 
     // Check if the wizard executable exists
     if !openmw_wizard_path.exists() {
@@ -306,69 +267,21 @@ pub async fn run_openmw_wizard(app_handle: tauri::AppHandle) -> Result<String, S
 
     log::info!("Running OpenMW wizard at: {}", openmw_wizard_path.display());
 
-    // Spawn the OpenMW wizard process
-    let mut child = std::process::Command::new(&openmw_wizard_path)
-        .spawn()
-        .map_err(|e| format!("Failed to run OpenMW wizard: {}", e))?;
-
-    let pid = child.id();
-    log::info!("OpenMW wizard started successfully (PID: {})", pid);
-
-    // Send initial event that wizard started
-    app_handle
-        .emit("openmw-wizard-started", &pid)
-        .map_err(|e| format!("Failed to emit wizard started event: {}", e))?;
-
-    // Spawn a task to monitor the process
-    tokio::spawn(async move {
-        // Wait for the process to complete
-        match child.wait() {
-            Ok(status) => {
-                let event_data = serde_json::json!({
-                    "pid": pid,
-                    "success": status.success(),
-                    "exit_code": status.code(),
-                    "message": if status.success() {
-                        "OpenMW wizard completed successfully"
-                    } else {
-                        "OpenMW wizard exited with an error"
-                    }
-                });
-
-                log::info!(
-                    "OpenMW wizard (PID: {}) exited with status: {:?}",
-                    pid,
-                    status.code()
-                );
-
-                // Emit the completion event
-                if let Err(e) = app_handle.emit("openmw-wizard-exited", &event_data) {
-                    log::error!("Failed to emit wizard exited event: {}", e);
-                }
-            }
-            Err(e) => {
-                let event_data = serde_json::json!({
-                    "pid": pid,
-                    "success": false,
-                    "exit_code": None::<i32>,
-                    "message": format!("Failed to wait for OpenMW wizard: {}", e)
-                });
-
-                log::error!("Failed to wait for OpenMW wizard (PID: {}): {}", pid, e);
-
-                // Emit the error event
-                if let Err(emit_err) = app_handle.emit("openmw-wizard-exited", &event_data) {
-                    log::error!("Failed to emit wizard error event: {}", emit_err);
-                }
-            }
-        }
-    });
+    let pid = supervisor.spawn(
+        &app_handle,
+        ProcessRole::Wizard,
+        tokio::process::Command::new(&openmw_wizard_path),
+        false,
+    )?;
 
     Ok(format!("OpenMW wizard started successfully (PID: {})", pid))
 }
 
 #[tauri::command]
-pub async fn run_openmw_launcher(app_handle: tauri::AppHandle) -> Result<String, String> {
+pub async fn run_openmw_launcher(
+    app_handle: tauri::AppHandle,
+    supervisor: tauri::State<'_, ProcessSupervisor>,
+) -> Result<String, String> {
     log::info!("Running OpenMW launcher");
 
     // Get the nerevar config to find the TES3MP installation path
@@ -379,7 +292,8 @@ pub async fn run_openmw_launcher(app_handle: tauri::AppHandle) -> Result<String,
     let tes3mp_path = config.tes3mp_path;
 
     // Construct the path to the OpenMW launcher executable
-    let openmw_launcher_path = Path::new(&tes3mp_path).join("openmw-launcher.exe");
+    let openmw_launcher_path =
+        Path::new(&tes3mp_path).join(Platform::current().exe_name("openmw-launcher"));
 
     // Check if the launcher executable exists
     if !openmw_launcher_path.exists() {
@@ -394,63 +308,12 @@ pub async fn run_openmw_launcher(app_handle: tauri::AppHandle) -> Result<String,
         openmw_launcher_path.display()
     );
 
-    // Spawn the OpenMW launcher process
-    let mut child = std::process::Command::new(&openmw_launcher_path)
-        .spawn()
-        .map_err(|e| format!("Failed to run OpenMW launcher: {}", e))?;
-
-    let pid = child.id();
-    log::info!("OpenMW launcher started successfully (PID: {})", pid);
-
-    // Send initial event that launcher started
-    app_handle
-        .emit("openmw-launcher-started", &pid)
-        .map_err(|e| format!("Failed to emit launcher started event: {}", e))?;
-
-    // Spawn a task to monitor the process
-    tokio::spawn(async move {
-        // Wait for the process to complete
-        match child.wait() {
-            Ok(status) => {
-                let event_data = serde_json::json!({
-                    "pid": pid,
-                    "success": status.success(),
-                    "exit_code": status.code(),
-                    "message": if status.success() {
-                        "OpenMW launcher completed successfully"
-                    } else {
-                        "OpenMW launcher exited with an error"
-                    }
-                });
-
-                log::info!(
-                    "OpenMW launcher (PID: {}) exited with status: {:?}",
-                    pid,
-                    status.code()
-                );
-
-                // Emit the completion event
-                if let Err(e) = app_handle.emit("openmw-launcher-exited", &event_data) {
-                    log::error!("Failed to emit launcher exited event: {}", e);
-                }
-            }
-            Err(e) => {
-                let event_data = serde_json::json!({
-                    "pid": pid,
-                    "success": false,
-                    "exit_code": None::<i32>,
-                    "message": format!("Failed to wait for OpenMW launcher: {}", e)
-                });
-
-                log::error!("Failed to wait for OpenMW launcher (PID: {}): {}", pid, e);
-
-                // Emit the error event
-                if let Err(emit_err) = app_handle.emit("openmw-launcher-exited", &event_data) {
-                    log::error!("Failed to emit launcher error event: {}", emit_err);
-                }
-            }
-        }
-    });
+    let pid = supervisor.spawn(
+        &app_handle,
+        ProcessRole::Launcher,
+        tokio::process::Command::new(&openmw_launcher_path),
+        false,
+    )?;
 
     Ok(format!(
         "OpenMW launcher started successfully (PID: {})",
@@ -459,165 +322,219 @@ pub async fn run_openmw_launcher(app_handle: tauri::AppHandle) -> Result<String,
 }
 
 #[tauri::command]
-pub async fn check_for_tes3mp_update() -> Result<UpdateCheckResponse, String> {
-    // Get config and check version, then hit the api and check the returned version, if the returned version is greater than the current version, return true, otherwise return false
+pub async fn check_for_tes3mp_update(
+    api: tauri::State<'_, ApiClient>,
+) -> Result<UpdateCheckResponse, String> {
+    // Get config and check version, then hit the api and compare the returned version against it as semver
     let config =
         get_nerevar_config().map_err(|e| format!("Failed to get Nerevar config: {}", e))?;
     let config = config.ok_or("No Nerevar config found. Please install TES3MP first.")?;
     let current_version = config.version;
-    let api_url = "https://nerevar.cc/api/v1";
-    let response = reqwest::get(format!("{}/releases/tes3mp", api_url))
+    let release = api
+        .get_json::<ReleaseInfo>(
+            "/releases/tes3mp",
+            &[
+                ("platform", Platform::current().as_str()),
+                ("channel", config.channel.as_str()),
+            ],
+        )
         .await
         .map_err(|e| format!("Failed to check for TES3MP update: {}", e))?;
-    let response = response
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| format!("Failed to parse TES3MP update response: {}", e))?;
-    let latest_version = response["version"].as_str().unwrap_or("0.0.0");
-    let download_url = response["url"].as_str().map(|s| s.to_string());
+    let latest_version = release.version.as_str();
+    let comparison = compare_versions(&current_version, latest_version);
     log::info!("Latest version: {}", latest_version);
     log::info!("Current version: {}", current_version);
-    Ok(UpdateCheckResponse {
-        update_available: current_version != latest_version,
+    log::info!("Latest release is: {:?}", comparison);
+
+    let asset = crate::installer::select_asset(&release, Platform::current()).ok();
+    let mut response = UpdateCheckResponse {
+        update_available: comparison == VersionComparison::Newer,
         version: latest_version.to_string(),
-        url: download_url,
-    })
+        url: asset.as_ref().map(|asset| asset.url.clone()),
+        current_version: current_version.to_string(),
+        comparison,
+        release_notes: None,
+        release_date: None,
+        download_size: download_size(&api, asset.as_ref()).await,
+        breaking: false,
+        changelog: Vec::new(),
+    };
+    add_release_notes(&api, "tes3mp", config.channel, &release, &mut response).await;
+    Ok(response)
 }
 
 #[tauri::command]
-pub async fn check_for_app_update() -> Result<UpdateCheckResponse, String> {
+pub async fn check_for_app_update(
+    api: tauri::State<'_, ApiClient>,
+    updates: tauri::State<'_, AppUpdates>,
+    allow_downgrade: Option<bool>,
+) -> Result<UpdateCheckResponse, String> {
     // Get the current app version from the built-in version
     let current_version = env!("CARGO_PKG_VERSION");
+    let channel = crate::config::current_channel();
 
-    let api_url = "https://nerevar.cc/api/v1";
-    let response = reqwest::get(format!("{}/releases/nerevar", api_url))
+    let release = api
+        .get_json::<ReleaseInfo>("/releases/nerevar", &[("channel", channel.as_str())])
         .await
         .map_err(|e| format!("Failed to check for Nerevar update: {}", e))?;
-    let response = response
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| format!("Failed to parse Nerevar update response: {}", e))?;
-    let latest_version = response["version"].as_str().unwrap_or("0.0.0");
-    let download_url = response["url"].as_str().map(|s| s.to_string());
+    let latest_version = release.version.as_str();
+    let comparison = compare_versions(current_version, latest_version);
     log::info!("Latest version: {}", latest_version);
     log::info!("Current version: {}", current_version);
-    Ok(UpdateCheckResponse {
-        update_available: current_version != latest_version,
+    log::info!("Latest release is: {:?}", comparison);
+
+    // Only the build for this platform, and only when it is newer, may be
+    // downloaded. An older build (after switching to a more stable channel) is
+    // only offered when the caller explicitly asks to downgrade.
+    let asset = crate::installer::select_asset(&release, Platform::current()).ok();
+    let update_available = match comparison {
+        VersionComparison::Newer => true,
+        VersionComparison::Older => allow_downgrade.unwrap_or(false),
+        _ => false,
+    };
+    if update_available && comparison == VersionComparison::Older {
+        log::warn!(
+            "Offering a downgrade to Nerevar {} on the {} channel",
+            latest_version,
+            channel.as_str()
+        );
+    }
+    updates.offer(
+        asset
+            .as_ref()
+            .filter(|_| update_available)
+            .map(|asset| AppUpdate {
+                version: release.version.clone(),
+                url: asset.url.clone(),
+                sha256: asset.sha256.clone(),
+                signature: asset.signature.clone(),
+            }),
+    );
+
+    let mut response = UpdateCheckResponse {
+        update_available,
         version: latest_version.to_string(),
-        url: download_url,
-    })
+        url: asset.as_ref().map(|asset| asset.url.clone()),
+        current_version: current_version.to_string(),
+        comparison,
+        release_notes: None,
+        release_date: None,
+        download_size: download_size(&api, asset.as_ref()).await,
+        breaking: false,
+        changelog: Vec::new(),
+    };
+    add_release_notes(&api, "nerevar", channel, &release, &mut response).await;
+    Ok(response)
 }
 
-#[tauri::command]
-pub async fn download_app_update(download_url: String) -> Result<String, String> {
-    log::info!("Starting download from: {}", download_url);
-
-    // Create a temporary file for the download
-    let temp_dir = std::env::temp_dir();
-    let temp_file_path = temp_dir.join("nerevar_update.exe");
-
-    // Download the file
-    let response = reqwest::get(&download_url)
-        .await
-        .map_err(|e| format!("Failed to download update: {}", e))?;
+// The size the release lists for the asset, or failing that, what the
+// download server reports
+async fn download_size(api: &ApiClient, asset: Option<&ReleaseAsset>) -> Option<u64> {
+    let asset = asset?;
+    match asset.size {
+        Some(size) => Some(size),
+        None => crate::preflight::content_length(api.http(), &asset.url).await,
+    }
+}
 
-    if !response.status().is_success() {
+#[tauri::command]
+pub async fn download_app_update(
+    app_handle: tauri::AppHandle,
+    api: tauri::State<'_, ApiClient>,
+    updates: tauri::State<'_, AppUpdates>,
+    operations: tauri::State<'_, OperationRegistry>,
+    download_url: String,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    let update = updates.offered()?;
+    if update.url != download_url {
         return Err(format!(
-            "Download failed with status: {}",
-            response.status()
+            "Refusing to download an update that was not offered: {}",
+            download_url
         ));
     }
 
-    let content = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Failed to read download content: {}", e))?;
+    let operation = operations.start(&app_handle, "app-update", operation_id)?;
+    log::info!(
+        "Starting download from: {} (operation {})",
+        download_url,
+        operation.id()
+    );
 
-    // Write directly to the temp file
-    fs::write(&temp_file_path, &content)
-        .map_err(|e| format!("Failed to write downloaded content: {}", e))?;
+    // Name the file after the version so a partial download is never resumed
+    // against a different update
+    crate::versions::validate_version_name(&update.version)?;
+    let temp_dir = std::env::temp_dir();
+    let temp_file_path =
+        temp_dir.join(Platform::current().exe_name(&format!("nerevar_update_{}", update.version)));
+
+    // Streamed to disk with progress events, resumed if the connection drops
+    download_to_file(
+        &app_handle,
+        api.http(),
+        &download_url,
+        &temp_file_path,
+        APP_UPDATE_PROGRESS_EVENT,
+        operation.token(),
+    )
+    .await
+    .map_err(|e| {
+        if operation.is_cancelled() {
+            CANCELLED.to_string()
+        } else {
+            format!("Failed to download update: {}", e)
+        }
+    })?;
+
+    // Refuse anything not signed with the release key before it goes near the install
+    if let Err(e) = verify_app_update(
+        &temp_file_path,
+        update.sha256.as_deref(),
+        update.signature.as_deref(),
+    ) {
+        let _ = fs::remove_file(&temp_file_path);
+        return Err(format!("Downloaded update was rejected: {}", e));
+    }
 
     let temp_path_str = temp_file_path.to_string_lossy().to_string();
+    updates.set_downloaded(update, temp_file_path)?;
 
     log::info!("Download completed to: {}", temp_path_str);
     Ok(temp_path_str)
 }
 
 #[tauri::command]
-pub async fn apply_app_update(temp_file_path: String) -> Result<String, String> {
-    log::info!("Applying update from: {}", temp_file_path);
-
-    // Get the current executable path
-    let current_exe = std::env::current_exe()
-        .map_err(|e| format!("Failed to get current executable path: {}", e))?;
-
-    let current_exe_str = current_exe.to_string_lossy().to_string();
-    log::info!("Current executable: {}", current_exe_str);
-
-    // Create a backup of the current executable
-    let backup_path = format!("{}.backup", current_exe_str);
-    fs::copy(&current_exe, &backup_path).map_err(|e| format!("Failed to create backup: {}", e))?;
-
-    log::info!("Created backup at: {}", backup_path);
-
-    // Create a batch script to handle the replacement after exit
-    let temp_dir = std::env::temp_dir();
-    let batch_script_path = temp_dir.join("nerevar_update.bat");
-
-    let batch_content = format!(
-        r#"@echo off
-timeout /t 2 /nobreak >nul
-copy "{}" "{}"
-if %errorlevel% equ 0 (
-    del "{}"
-    del "%~f0"
-    start "" "{}"
-) else (
-    echo Update failed, restoring backup...
-    copy "{}" "{}"
-    del "{}"
-    del "%~f0"
-)
-"#,
-        temp_file_path,  // Source (new exe)
-        current_exe_str, // Destination (current exe)
-        temp_file_path,  // Delete temp file
-        current_exe_str, // Start new exe
-        backup_path,     // Restore from backup
-        current_exe_str, // Restore to current exe
-        backup_path      // Delete backup
+pub async fn apply_app_update(
+    app_handle: tauri::AppHandle,
+    updates: tauri::State<'_, AppUpdates>,
+) -> Result<String, String> {
+    // Only the verified download of the offered update is ever applied
+    let (update, temp_file_path) = updates.take_downloaded()?;
+    log::info!(
+        "Applying update {} from: {}",
+        update.version,
+        temp_file_path.display()
     );
 
-    fs::write(&batch_script_path, batch_content)
-        .map_err(|e| format!("Failed to create batch script: {}", e))?;
+    let version = crate::updater::install_update(&temp_file_path, &update).await?;
 
-    log::info!("Created batch script at: {}", batch_script_path.display());
-
-    // Start the batch script
-    let mut cmd = Command::new("cmd");
-    cmd.args(["/c", &batch_script_path.to_string_lossy()]);
-    cmd.stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null());
-
-    // On Windows, we need to detach the process
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
-        cmd.creation_flags(0x00000008); // CREATE_NEW_PROCESS_GROUP
-    }
-
-    cmd.spawn()
-        .map_err(|e| format!("Failed to start update script: {}", e))?;
+    log::info!("Restarting into Nerevar {}", version);
+    app_handle.restart();
+}
 
-    log::info!("Update script started, exiting current process");
+#[tauri::command]
+pub async fn rollback_app_update(app_handle: tauri::AppHandle) -> Result<String, String> {
+    crate::updater::rollback_update()?;
 
-    // Exit the current process
-    std::process::exit(0);
+    log::info!("Restarting into the previous version of Nerevar");
+    app_handle.restart();
 }
 
 #[tauri::command]
-pub async fn run_tes3mp_browser(app_handle: tauri::AppHandle) -> Result<String, String> {
+pub async fn run_tes3mp_browser(
+    app_handle: tauri::AppHandle,
+    supervisor: tauri::State<'_, ProcessSupervisor>,
+) -> Result<String, String> {
     log::info!("Running TES3MP browser");
 
     // Get the nerevar config to find the TES3MP installation path
@@ -628,7 +545,8 @@ pub async fn run_tes3mp_browser(app_handle: tauri::AppHandle) -> Result<String,
     let tes3mp_path = config.tes3mp_path;
 
     // Construct the path to the TES3MP browser executable
-    let server_browser_path = Path::new(&tes3mp_path).join("tes3mp-browser.exe");
+    let server_browser_path =
+        Path::new(&tes3mp_path).join(Platform::current().exe_name("tes3mp-browser"));
 
     // Check if the TES3MP browser executable exists
     if !server_browser_path.exists() {
@@ -643,63 +561,12 @@ pub async fn run_tes3mp_browser(app_handle: tauri::AppHandle) -> Result<String,
         server_browser_path.display()
     );
 
-    // Spawn the TES3MP browser process
-    let mut child = std::process::Command::new(&server_browser_path)
-        .spawn()
-        .map_err(|e| format!("Failed to run TES3MP browser: {}", e))?;
-
-    let pid = child.id();
-    log::info!("TES3MP browser started successfully (PID: {})", pid);
-
-    // Send initial event that TES3MP browser started
-    app_handle
-        .emit("tes3mp-browser-started", &pid)
-        .map_err(|e| format!("Failed to emit TES3MP browser started event: {}", e))?;
-
-    // Spawn a task to monitor the process
-    tokio::spawn(async move {
-        // Wait for the process to complete
-        match child.wait() {
-            Ok(status) => {
-                let event_data = serde_json::json!({
-                    "pid": pid,
-                    "success": status.success(),
-                    "exit_code": status.code(),
-                    "message": if status.success() {
-                        "TES3MP browser completed successfully"
-                    } else {
-                        "TES3MP browser exited with an error"
-                    }
-                });
-
-                log::info!(
-                    "TES3MP browser (PID: {}) exited with status: {:?}",
-                    pid,
-                    status.code()
-                );
-
-                // Emit the completion event
-                if let Err(e) = app_handle.emit("tes3mp-browser-exited", &event_data) {
-                    log::error!("Failed to emit TES3MP browser exited event: {}", e);
-                }
-            }
-            Err(e) => {
-                let event_data = serde_json::json!({
-                    "pid": pid,
-                    "success": false,
-                    "exit_code": None::<i32>,
-                    "message": format!("Failed to wait for TES3MP browser: {}", e)
-                });
-
-                log::error!("Failed to wait for TES3MP browser (PID: {}): {}", pid, e);
-
-                // Emit the error event
-                if let Err(emit_err) = app_handle.emit("tes3mp-browser-exited", &event_data) {
-                    log::error!("Failed to emit TES3MP browser error event: {}", emit_err);
-                }
-            }
-        }
-    });
+    let pid = supervisor.spawn(
+        &app_handle,
+        ProcessRole::Browser,
+        tokio::process::Command::new(&server_browser_path),
+        false,
+    )?;
 
     Ok(format!(
         "TES3MP browser started successfully (PID: {})",
@@ -708,18 +575,19 @@ pub async fn run_tes3mp_browser(app_handle: tauri::AppHandle) -> Result<String,
 }
 
 #[tauri::command]
-pub async fn run_tes3mp(app_handle: tauri::AppHandle) -> Result<String, String> {
+pub async fn run_tes3mp(
+    app_handle: tauri::AppHandle,
+    supervisor: tauri::State<'_, ProcessSupervisor>,
+    server: Option<String>,
+    allow_duplicate: Option<bool>,
+) -> Result<String, String> {
     log::info!("Running TES3MP");
 
-    // Get the nerevar config to find the TES3MP installation path
-    let nerevar_config =
-        get_nerevar_config().map_err(|e| format!("Failed to get Nerevar config: {}", e))?;
-
-    let config = nerevar_config.ok_or("No Nerevar config found. Please install TES3MP first.")?;
-    let tes3mp_path = config.tes3mp_path;
+    // Use the version pinned for the server being joined, if any
+    let install_dir = crate::versions::resolve_install_dir(server.as_deref())?;
 
     // Construct the path to the TES3MP executable
-    let tes3mp_path = Path::new(&tes3mp_path).join("tes3mp.exe");
+    let tes3mp_path = install_dir.join(Platform::current().exe_name("tes3mp"));
 
     // Check if the TES3MP executable exists
     if !tes3mp_path.exists() {
@@ -728,63 +596,12 @@ pub async fn run_tes3mp(app_handle: tauri::AppHandle) -> Result<String, String>
 
     log::info!("Running TES3MP at: {}", tes3mp_path.display());
 
-    // Spawn the TES3MP process
-    let mut child = std::process::Command::new(&tes3mp_path)
-        .spawn()
-        .map_err(|e| format!("Failed to run TES3MP: {}", e))?;
-
-    let pid = child.id();
-    log::info!("TES3MP started successfully (PID: {})", pid);
-
-    // Send initial event that TES3MP started
-    app_handle
-        .emit("tes3mp-started", &pid)
-        .map_err(|e| format!("Failed to emit TES3MP started event: {}", e))?;
-
-    // Spawn a task to monitor the process
-    tokio::spawn(async move {
-        // Wait for the process to complete
-        match child.wait() {
-            Ok(status) => {
-                let event_data = serde_json::json!({
-                    "pid": pid,
-                    "success": status.success(),
-                    "exit_code": status.code(),
-                    "message": if status.success() {
-                        "TES3MP completed successfully"
-                    } else {
-                        "TES3MP exited with an error"
-                    }
-                });
-
-                log::info!(
-                    "TES3MP (PID: {}) exited with status: {:?}",
-                    pid,
-                    status.code()
-                );
-
-                // Emit the completion event
-                if let Err(e) = app_handle.emit("tes3mp-exited", &event_data) {
-                    log::error!("Failed to emit TES3MP exited event: {}", e);
-                }
-            }
-            Err(e) => {
-                let event_data = serde_json::json!({
-                    "pid": pid,
-                    "success": false,
-                    "exit_code": None::<i32>,
-                    "message": format!("Failed to wait for TES3MP: {}", e)
-                });
-
-                log::error!("Failed to wait for TES3MP (PID: {}): {}", pid, e);
-
-                // Emit the error event
-                if let Err(emit_err) = app_handle.emit("tes3mp-exited", &event_data) {
-                    log::error!("Failed to emit TES3MP error event: {}", emit_err);
-                }
-            }
-        }
-    });
+    let pid = supervisor.spawn(
+        &app_handle,
+        ProcessRole::Client,
+        tokio::process::Command::new(&tes3mp_path),
+        allow_duplicate.unwrap_or(false),
+    )?;
 
     Ok(format!("TES3MP started successfully (PID: {})", pid))
 }
@@ -795,11 +612,12 @@ pub async fn set_tes3mp_client_config(
     port: u16,
     password: String,
 ) -> Result<bool, String> {
-    // Get the AppData directory for Nerevar
-    let appdata_dir = get_appdata_dir()?;
+    // Write to the install that will be launched for this server
+    let server = format!("{}:{}", ip, port);
+    let install_dir = crate::versions::resolve_install_dir(Some(&server))?;
 
     // Construct the path to the TES3MP client config file
-    let config_path = appdata_dir.join("TES3MP").join("tes3mp-client-default.cfg");
+    let config_path = install_dir.join("tes3mp-client-default.cfg");
 
     // Check if the config file exists
     if !config_path.exists() {
@@ -833,11 +651,11 @@ pub async fn set_tes3mp_client_config(
 
 #[tauri::command]
 pub async fn get_tes3mp_server_config() -> Result<serde_json::Value, String> {
-    // Get the AppData directory for Nerevar
-    let appdata_dir = get_appdata_dir()?;
+    // Get the active TES3MP install
+    let install_dir = crate::versions::active_install_dir()?;
 
     // Construct the path to the TES3MP server config file
-    let config_path = appdata_dir.join("TES3MP").join("tes3mp-server-default.cfg");
+    let config_path = install_dir.join("tes3mp-server-default.cfg");
 
     // Check if the config file exists
     if !config_path.exists() {
@@ -863,11 +681,11 @@ pub async fn get_tes3mp_server_config() -> Result<serde_json::Value, String> {
 
 #[tauri::command]
 pub async fn set_tes3mp_server_config(config: serde_json::Value) -> Result<bool, String> {
-    // Get the AppData directory for Nerevar
-    let appdata_dir = get_appdata_dir()?;
+    // Get the active TES3MP install
+    let install_dir = crate::versions::active_install_dir()?;
 
     // Construct the path to the TES3MP server config file
-    let config_path = appdata_dir.join("TES3MP").join("tes3mp-server-default.cfg");
+    let config_path = install_dir.join("tes3mp-server-default.cfg");
 
     // Check if the config file exists
     if !config_path.exists() {
@@ -894,12 +712,11 @@ pub async fn set_tes3mp_server_config(config: serde_json::Value) -> Result<bool,
 
 #[tauri::command]
 pub async fn get_tes3mp_server_settings() -> Result<serde_json::Value, String> {
-    // Get the AppData directory for Nerevar
-    let appdata_dir = get_appdata_dir()?;
+    // Get the active TES3MP install
+    let install_dir = crate::versions::active_install_dir()?;
 
     // Construct the path to the TES3MP server config file
-    let config_path = appdata_dir
-        .join("TES3MP")
+    let config_path = install_dir
         .join("server")
         .join("scripts")
         .join("config.lua");
@@ -928,12 +745,11 @@ pub async fn get_tes3mp_server_settings() -> Result<serde_json::Value, String> {
 
 #[tauri::command]
 pub async fn set_tes3mp_server_settings(settings: serde_json::Value) -> Result<bool, String> {
-    // Get the AppData directory for Nerevar
-    let appdata_dir = get_appdata_dir()?;
+    // Get the active TES3MP install
+    let install_dir = crate::versions::active_install_dir()?;
 
     // Construct the path to the TES3MP server config file
-    let config_path = appdata_dir
-        .join("TES3MP")
+    let config_path = install_dir
         .join("server")
         .join("scripts")
         .join("config.lua");
@@ -959,109 +775,161 @@ pub async fn set_tes3mp_server_settings(settings: serde_json::Value) -> Result<b
 }
 
 #[tauri::command]
-pub async fn run_tes3mp_server(app_handle: tauri::AppHandle) -> Result<String, String> {
+pub async fn run_tes3mp_server(
+    app_handle: tauri::AppHandle,
+    watchdog: tauri::State<'_, ServerWatchdog>,
+    allow_duplicate: Option<bool>,
+) -> Result<String, String> {
     log::info!("Running TES3MP server");
 
-    // Get the nerevar config to find the TES3MP installation path
-    let nerevar_config =
-        get_nerevar_config().map_err(|e| format!("Failed to get Nerevar config: {}", e))?;
+    // Starting the server by hand takes over from the watchdog
+    watchdog.reset();
+    let pid = crate::server::start_server(&app_handle, allow_duplicate.unwrap_or(false))?;
 
-    let config = nerevar_config.ok_or("No Nerevar config found. Please install TES3MP first.")?;
-    let tes3mp_path = config.tes3mp_path;
+    Ok(format!("TES3MP server started successfully (PID: {})", pid))
+}
 
-    // Construct the path to the TES3MP server executable
-    let server_path = Path::new(&tes3mp_path).join("tes3mp-server.exe");
+#[tauri::command]
+pub fn get_watchdog_status(watchdog: tauri::State<'_, ServerWatchdog>) -> WatchdogStatus {
+    watchdog.status()
+}
 
-    // Check if the TES3MP server executable exists
-    if !server_path.exists() {
-        return Err(format!(
-            "TES3MP server not found at: {}",
-            server_path.display()
-        ));
+#[tauri::command]
+pub fn set_watchdog_policy(
+    watchdog: tauri::State<'_, ServerWatchdog>,
+    policy: WatchdogPolicy,
+) -> Result<NerevarConfig, String> {
+    let config = crate::config::set_watchdog_policy(policy)?;
+    if !config.watchdog.enabled {
+        watchdog.reset();
+    }
+    Ok(config)
+}
+
+#[tauri::command]
+pub fn reset_watchdog(watchdog: tauri::State<'_, ServerWatchdog>) {
+    watchdog.reset();
+}
+
+#[tauri::command]
+pub fn get_schedules(
+    scheduler: tauri::State<'_, Scheduler>,
+) -> Result<Vec<ScheduleStatus>, String> {
+    scheduler.status()
+}
+
+#[tauri::command]
+pub fn set_schedules(tasks: Vec<ScheduledTask>) -> Result<NerevarConfig, String> {
+    crate::config::set_schedules(tasks)
+}
+
+#[tauri::command]
+pub async fn run_scheduled_task(
+    app_handle: tauri::AppHandle,
+    task_id: String,
+) -> Result<ScheduledRun, String> {
+    let config =
+        get_nerevar_config()?.ok_or("No Nerevar config found. Please install TES3MP first.")?;
+    let task = config
+        .schedules
+        .into_iter()
+        .find(|task| task.id == task_id)
+        .ok_or(format!("No scheduled task with ID: {}", task_id))?;
+
+    Ok(crate::scheduler::run_task(&app_handle, &task).await)
+}
+
+#[tauri::command]
+pub fn get_server_log(
+    server_log: tauri::State<'_, ServerLog>,
+    limit: Option<usize>,
+) -> Vec<ServerLogLine> {
+    server_log.lines(limit)
+}
+
+#[tauri::command]
+pub fn clear_server_log(server_log: tauri::State<'_, ServerLog>) {
+    server_log.clear();
+}
+
+#[tauri::command]
+pub async fn send_server_command(
+    app_handle: tauri::AppHandle,
+    supervisor: tauri::State<'_, ProcessSupervisor>,
+    server_log: tauri::State<'_, ServerLog>,
+    command: String,
+    pid: Option<u32>,
+) -> Result<(), String> {
+    let command = command.trim();
+    if command.is_empty() {
+        return Err("Command is empty".to_string());
+    }
+    // A newline would send the rest as a second command
+    if command.contains(['\r', '\n']) {
+        return Err("Send one command at a time".to_string());
     }
 
-    log::info!("Running TES3MP server at: {}", server_path.display());
-
-    // Spawn the TES3MP server process in its own terminal window
-    // Use PowerShell to properly handle the start command with quotes
-    let mut child = std::process::Command::new("powershell.exe")
-        .args([
-            "-Command",
-            &format!(
-                "Start-Process -FilePath '{}' -WindowStyle Normal -PassThru | Wait-Process",
-                server_path.to_string_lossy()
-            ),
-        ])
-        .spawn()
-        .map_err(|e| format!("Failed to run TES3MP server in terminal: {}", e))?;
-
-    let pid = child.id();
+    let pid = match pid {
+        Some(pid) => pid,
+        None => {
+            supervisor
+                .running(ProcessRole::Server)
+                .ok_or("TES3MP server is not running")?
+                .pid
+        }
+    };
+
     log::info!(
-        "TES3MP server started successfully in terminal (PID: {})",
-        pid
+        "Sending command to TES3MP server (PID: {}): {}",
+        pid,
+        command
     );
+    supervisor.write_stdin(pid, command).await?;
+    server_log.record_command(&app_handle, pid, command);
+    Ok(())
+}
 
-    // Send initial event that TES3MP server started
-    app_handle
-        .emit("tes3mp-server-started", &pid)
-        .map_err(|e| format!("Failed to emit TES3MP server started event: {}", e))?;
-
-    // Spawn a task to monitor the process
-    tokio::spawn(async move {
-        // Wait for the process to complete
-        match child.wait() {
-            Ok(status) => {
-                let event_data = serde_json::json!({
-                    "pid": pid,
-                    "success": status.success(),
-                    "exit_code": status.code(),
-                    "message": if status.success() {
-                        "TES3MP server completed successfully"
-                    } else {
-                        "TES3MP server exited with an error"
-                    }
-                });
-
-                log::info!(
-                    "TES3MP server (PID: {}) exited with status: {:?}",
-                    pid,
-                    status.code()
-                );
-
-                // Emit the completion event
-                if let Err(e) = app_handle.emit("tes3mp-server-exited", &event_data) {
-                    log::error!("Failed to emit TES3MP server exited event: {}", e);
-                }
-            }
-            Err(e) => {
-                let event_data = serde_json::json!({
-                    "pid": pid,
-                    "success": false,
-                    "exit_code": None::<i32>,
-                    "message": format!("Failed to wait for TES3MP server: {}", e)
-                });
-
-                log::error!("Failed to wait for TES3MP server (PID: {}): {}", pid, e);
-
-                // Emit the error event
-                if let Err(emit_err) = app_handle.emit("tes3mp-server-exited", &event_data) {
-                    log::error!("Failed to emit TES3MP server error event: {}", emit_err);
-                }
-            }
-        }
-    });
+#[tauri::command]
+pub fn get_server_command_history(server_log: tauri::State<'_, ServerLog>) -> Vec<String> {
+    server_log.history()
+}
 
-    Ok(format!("TES3MP server started successfully (PID: {})", pid))
+#[tauri::command]
+pub fn list_processes(supervisor: tauri::State<'_, ProcessSupervisor>) -> Vec<ProcessInfo> {
+    supervisor.list()
+}
+
+#[tauri::command]
+pub fn get_process_stats(
+    stats: tauri::State<'_, ProcessStatsHistory>,
+    pid: Option<u32>,
+) -> Vec<ProcessStats> {
+    stats.get(pid)
+}
+
+#[tauri::command]
+pub async fn stop_process(
+    supervisor: tauri::State<'_, ProcessSupervisor>,
+    pid: u32,
+) -> Result<(), String> {
+    supervisor.stop(pid).await
+}
+
+#[tauri::command]
+pub async fn kill_process(
+    supervisor: tauri::State<'_, ProcessSupervisor>,
+    pid: u32,
+) -> Result<(), String> {
+    supervisor.kill(pid).await
 }
 
 #[tauri::command]
 pub async fn open_config_lua_in_explorer() -> Result<bool, String> {
-    // Get the AppData directory for Nerevar
-    let appdata_dir = get_appdata_dir()?;
+    // Get the active TES3MP install
+    let install_dir = crate::versions::active_install_dir()?;
 
     // Construct the path to the TES3MP server config file
-    let config_path = appdata_dir
-        .join("TES3MP")
+    let config_path = install_dir
         .join("server")
         .join("scripts")
         .join("config.lua");
@@ -1,4 +1,5 @@
-use crate::types::{Mode, NerevarConfig, OpenMWConfig};
+use crate::cron::CronSchedule;
+use crate::types::{Channel, Mode, NerevarConfig, OpenMWConfig, ScheduledTask, WatchdogPolicy};
 use crate::utils::{get_appdata_dir, get_documents_folder};
 use std::fs;
 
@@ -21,6 +22,25 @@ pub fn get_nerevar_config() -> Result<Option<NerevarConfig>, String> {
     Ok(Some(config))
 }
 
+pub fn save_nerevar_config(config: &NerevarConfig) -> Result<(), String> {
+    let appdata_dir = get_appdata_dir()?;
+    let config_path = appdata_dir.join("config.json");
+
+    let config_json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+    // Write to a temp file and rename over the old config, so a failed write
+    // never leaves a truncated config.json behind
+    let temp_path = appdata_dir.join("config.json.tmp");
+    fs::write(&temp_path, config_json)
+        .map_err(|e| format!("Failed to write config file: {}", e))?;
+    fs::rename(&temp_path, &config_path)
+        .map_err(|e| format!("Failed to replace config file: {}", e))?;
+
+    log::info!("Nerevar config updated: {}", config_path.display());
+    Ok(())
+}
+
 pub fn get_openmw_config() -> Result<Option<OpenMWConfig>, String> {
     // Get the Documents folder using dirs crate
     let documents_dir = get_documents_folder()?;
@@ -116,6 +136,111 @@ pub fn set_mode(mode: Mode) -> Result<String, String> {
     Ok(format!("Mode set to: {:?}", mode))
 }
 
+/// Points Nerevar at a self-hosted API (`api_url`), the local stand-in
+/// (`use_local_api`), or back at nerevar.cc when both are unset.
+pub fn set_api_settings(
+    api_url: Option<String>,
+    use_local_api: bool,
+) -> Result<NerevarConfig, String> {
+    let mut config = get_nerevar_config()?
+        .ok_or("No config file found. Please install TES3MP first.".to_string())?;
+
+    config.api_url = match api_url.as_deref().map(str::trim) {
+        Some(url) if !url.is_empty() => Some(crate::api::validate_api_url(url)?),
+        _ => None,
+    };
+    config.use_local_api = use_local_api;
+    save_nerevar_config(&config)?;
+
+    log::info!("Nerevar API URL set to: {}", crate::api::resolve_api_url());
+    Ok(config)
+}
+
+/// The configured release channel, stable when there is no config yet.
+pub fn current_channel() -> Channel {
+    match get_nerevar_config() {
+        Ok(Some(config)) => config.channel,
+        Ok(None) => Channel::Stable,
+        Err(e) => {
+            log::warn!("Failed to read Nerevar config, using stable channel: {}", e);
+            Channel::Stable
+        }
+    }
+}
+
+/// Switches the release channel. Nothing is downgraded by switching to a more
+/// stable channel: installed builds are kept until the channel catches up, or
+/// until an update is applied with the downgrade explicitly allowed.
+pub fn set_release_channel(channel: Channel) -> Result<NerevarConfig, String> {
+    let mut config = get_nerevar_config()?
+        .ok_or("No config file found. Please install TES3MP first.".to_string())?;
+
+    if channel < config.channel {
+        log::warn!(
+            "Switching from {} to {} channel, installed builds are kept until {} catches up",
+            config.channel.as_str(),
+            channel.as_str(),
+            channel.as_str()
+        );
+    }
+    config.channel = channel;
+    save_nerevar_config(&config)?;
+
+    log::info!("Release channel set to: {}", channel.as_str());
+    Ok(config)
+}
+
+/// Replaces the server watchdog policy.
+pub fn set_watchdog_policy(policy: WatchdogPolicy) -> Result<NerevarConfig, String> {
+    if policy.max_restarts == 0 || policy.window_seconds == 0 {
+        return Err("Restart limit and window must be greater than zero".to_string());
+    }
+    if policy.initial_backoff_seconds > policy.max_backoff_seconds {
+        return Err("Initial backoff can't be longer than the maximum backoff".to_string());
+    }
+
+    let mut config = get_nerevar_config()?
+        .ok_or("No config file found. Please install TES3MP first.".to_string())?;
+    config.watchdog = policy;
+    save_nerevar_config(&config)?;
+
+    log::info!(
+        "Server watchdog {}",
+        if config.watchdog.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+    Ok(config)
+}
+
+/// Replaces the scheduled tasks. Every task needs a unique ID and a valid
+/// cron expression.
+pub fn set_schedules(tasks: Vec<ScheduledTask>) -> Result<NerevarConfig, String> {
+    let mut ids = std::collections::HashSet::new();
+    for task in &tasks {
+        if task.id.trim().is_empty() {
+            return Err("Scheduled tasks need an ID".to_string());
+        }
+        if !ids.insert(task.id.as_str()) {
+            return Err(format!(
+                "More than one scheduled task has the ID: {}",
+                task.id
+            ));
+        }
+        CronSchedule::parse(&task.cron)?;
+    }
+
+    let mut config = get_nerevar_config()?
+        .ok_or("No config file found. Please install TES3MP first.".to_string())?;
+    config.schedules = tasks;
+    save_nerevar_config(&config)?;
+
+    log::info!("Saved {} scheduled tasks", config.schedules.len());
+    Ok(config)
+}
+
 pub fn update_config_values(
     content: &str,
     ip: &str,
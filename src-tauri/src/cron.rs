@@ -0,0 +1,153 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Timelike};
+
+// How far ahead `next_after` looks; a `29 2` schedule can be four years out
+const SEARCH_DAYS: i64 = 4 * 366;
+
+/// A cron expression: `minute hour day-of-month month day-of-week`, or one of
+/// `@hourly`, `@daily`, `@weekly` and `@monthly`.
+///
+/// Fields take `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps (`*/15`,
+/// `0-30/10`). Day of week is 0-7, with both 0 and 7 meaning Sunday. As in cron,
+/// when both day fields are restricted a day matching either of them matches.
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // Whether the day fields were given as something other than `*`
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            expression => expression,
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Cron expression must have 5 fields (minute hour day month weekday): {}",
+                expression
+            ));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7, "day of week")?;
+        // 7 is another name for Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days: parse_field(day, 1, 31, "day of month")?,
+            months: parse_field(month, 1, 12, "month")?,
+            weekdays,
+            days_restricted: !day.starts_with('*'),
+            weekdays_restricted: !weekday.starts_with('*'),
+        })
+    }
+
+    /// Whether the schedule fires in the minute of `time`.
+    pub fn matches(&self, time: &DateTime<Local>) -> bool {
+        has(self.minutes, time.minute())
+            && has(self.hours, time.hour())
+            && self.matches_day(time.date_naive())
+    }
+
+    /// The first minute after `after` the schedule fires in.
+    pub fn next_after(&self, after: &DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.naive_local() + chrono::Duration::minutes(1);
+        let start_date = start.date();
+
+        for offset in 0..SEARCH_DAYS {
+            let date = start_date + chrono::Duration::days(offset);
+            if !self.matches_day(date) {
+                continue;
+            }
+
+            let first_hour = if offset == 0 { start.hour() } else { 0 };
+            for hour in (first_hour..24).filter(|h| has(self.hours, *h)) {
+                let first_minute = if offset == 0 && hour == start.hour() {
+                    start.minute()
+                } else {
+                    0
+                };
+                for minute in (first_minute..60).filter(|m| has(self.minutes, *m)) {
+                    // Times skipped by a DST change don't exist, try the next one
+                    let Some(naive) = date.and_hms_opt(hour, minute, 0) else {
+                        continue;
+                    };
+                    if let Some(time) = Local.from_local_datetime(&naive).earliest() {
+                        return Some(time);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if !has(self.months, date.month()) {
+            return false;
+        }
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+// Parses one field into a bit mask of the values it allows
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid {} in cron expression: {}", name, field);
+    let number = |text: &str| text.parse::<u32>().map_err(|_| invalid());
+
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(number(step)?)),
+            None => (part, None),
+        };
+        let step = step.unwrap_or(1);
+        if step == 0 {
+            return Err(invalid());
+        }
+
+        let (low, high) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((low, high)) => (number(low)?, number(high)?),
+                // `5/15` means from 5 to the end in steps of 15
+                None if part.contains('/') => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+        if low < min || high > max || low > high {
+            return Err(format!(
+                "Cron {} must be between {} and {}: {}",
+                name, min, max, field
+            ));
+        }
+
+        for value in (low..=high).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
@@ -0,0 +1,224 @@
+use crate::operations::CANCELLED;
+use crate::types::DownloadProgress;
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
+
+// How many times an interrupted transfer is resumed before giving up
+const MAX_ATTEMPTS: u32 = 5;
+// Minimum time between two progress events
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+// A transfer that sends nothing for this long is treated as dropped
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Path of the partial file kept next to `dest` while a download is in flight.
+pub fn partial_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// Streams `url` to `dest` chunk by chunk, emitting `event` with a
+/// [`DownloadProgress`] payload as bytes arrive.
+///
+/// The body is written to `<dest>.part` first. If that file already exists
+/// (from an earlier interrupted download) the transfer is resumed with an
+/// HTTP Range request, and a dropped connection is resumed the same way.
+/// The partial file is only renamed to `dest` once the transfer completes.
+///
+/// Cancelling `cancel` stops the transfer and deletes the partial file.
+pub async fn download_to_file(
+    app_handle: &tauri::AppHandle,
+    client: &reqwest::Client,
+    url: &str,
+    dest: &Path,
+    event: &str,
+    cancel: &CancellationToken,
+) -> Result<u64, String> {
+    let part_path = partial_path(dest);
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+        let result = tokio::select! {
+            _ = cancel.cancelled() => Err(CANCELLED.to_string()),
+            result = download_attempt(app_handle, client, url, &part_path, event) => result,
+        };
+
+        match result {
+            Err(e) if cancel.is_cancelled() => {
+                log::info!("Download cancelled: {}", url);
+                if let Err(remove_err) = fs::remove_file(&part_path).await {
+                    if remove_err.kind() != std::io::ErrorKind::NotFound {
+                        log::warn!("Failed to remove partial download: {}", remove_err);
+                    }
+                }
+                return Err(e);
+            }
+            Ok(total) => {
+                fs::rename(&part_path, dest)
+                    .await
+                    .map_err(|e| format!("Failed to move downloaded file into place: {}", e))?;
+                log::info!("Download complete: {} ({} bytes)", dest.display(), total);
+                return Ok(total);
+            }
+            Err(e) if attempt < MAX_ATTEMPTS => {
+                log::warn!(
+                    "Download attempt {}/{} failed: {}, resuming",
+                    attempt,
+                    MAX_ATTEMPTS,
+                    e
+                );
+                tokio::select! {
+                    _ = cancel.cancelled() => {}
+                    _ = tokio::time::sleep(Duration::from_secs(attempt as u64)) => {}
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+async fn download_attempt(
+    app_handle: &tauri::AppHandle,
+    client: &reqwest::Client,
+    url: &str,
+    part_path: &Path,
+    event: &str,
+) -> Result<u64, String> {
+    let existing = match fs::metadata(part_path).await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    };
+
+    let mut request = client.get(url);
+    if existing > 0 {
+        log::info!(
+            "Resuming download from byte {}: {}",
+            existing,
+            part_path.display()
+        );
+        request = request.header(RANGE, format!("bytes={}-", existing));
+    }
+
+    let mut response = tokio::time::timeout(STALL_TIMEOUT, request.send())
+        .await
+        .map_err(|_| "Timed out waiting for the server to respond".to_string())?
+        .map_err(|e| format!("Failed to download file: {}", e))?;
+
+    let status = response.status();
+
+    // The server says the range starts past the end, so the partial file
+    // already holds the whole body (or is garbage); start over if unsure
+    if status == StatusCode::RANGE_NOT_SATISFIABLE {
+        let complete_length = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit('/').next())
+            .and_then(|value| value.parse::<u64>().ok());
+        if complete_length == Some(existing) {
+            return Ok(existing);
+        }
+        fs::remove_file(part_path)
+            .await
+            .map_err(|e| format!("Failed to remove stale partial download: {}", e))?;
+        return Err("Partial download did not match the remote file".to_string());
+    }
+
+    if !status.is_success() {
+        return Err(format!("Download failed with status: {}", status));
+    }
+
+    // A 200 means the server ignored the Range header and is sending everything
+    let resumed = status == StatusCode::PARTIAL_CONTENT;
+    let mut bytes_done = if resumed { existing } else { 0 };
+
+    let total = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(|length| length + bytes_done);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(part_path)
+        .await
+        .map_err(|e| format!("Failed to open download file: {}", e))?;
+
+    let started = Instant::now();
+    let started_at = bytes_done;
+    let mut last_emit = Instant::now();
+
+    while let Some(chunk) = tokio::time::timeout(STALL_TIMEOUT, response.chunk())
+        .await
+        .map_err(|_| "Download stalled".to_string())?
+        .map_err(|e| format!("Failed to read response bytes: {}", e))?
+    {
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write download file: {}", e))?;
+        bytes_done += chunk.len() as u64;
+
+        if last_emit.elapsed() >= PROGRESS_INTERVAL {
+            emit_progress(app_handle, event, bytes_done, total, started, started_at);
+            last_emit = Instant::now();
+        }
+    }
+
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write download file: {}", e))?;
+
+    if let Some(total) = total {
+        if bytes_done < total {
+            return Err(format!(
+                "Download ended early ({} of {} bytes)",
+                bytes_done, total
+            ));
+        }
+    }
+
+    emit_progress(app_handle, event, bytes_done, total, started, started_at);
+    Ok(bytes_done)
+}
+
+fn emit_progress(
+    app_handle: &tauri::AppHandle,
+    event: &str,
+    bytes_done: u64,
+    total: Option<u64>,
+    started: Instant,
+    started_at: u64,
+) {
+    let elapsed = started.elapsed().as_secs_f64();
+    let rate = if elapsed > 0.0 {
+        (bytes_done - started_at) as f64 / elapsed
+    } else {
+        0.0
+    };
+    let eta_seconds = match total {
+        Some(total) if rate > 0.0 => Some((total.saturating_sub(bytes_done) as f64 / rate) as u64),
+        _ => None,
+    };
+
+    let progress = DownloadProgress {
+        bytes_done,
+        total,
+        rate,
+        eta_seconds,
+    };
+
+    if let Err(e) = app_handle.emit(event, &progress) {
+        log::error!("Failed to emit download progress event: {}", e);
+    }
+}
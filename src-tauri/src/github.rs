@@ -0,0 +1,163 @@
+use crate::types::{Channel, Platform, ReleaseAsset, ReleaseInfo};
+use serde::Deserialize;
+
+// Built-in fallback source for TES3MP releases, used when the Nerevar API
+// can't be reached or none of the sources it lists work
+const GITHUB_RELEASES_API: &str = "https://api.github.com/repos/TES3MP/TES3MP/releases";
+
+// SHA-256 of the release assets GitHub has no digest for, keyed by asset name.
+// Add an entry for every TES3MP release Nerevar should be able to install
+// from GitHub alone, hashed from the archive on the releases page; assets
+// with neither a digest nor an entry here are refused.
+const PINNED_CHECKSUMS: &[(&str, &str)] = &[];
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    assets: Vec<GithubAsset>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    published_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+    size: u64,
+    // `sha256:<hex>`, missing on assets uploaded before GitHub recorded digests
+    #[serde(default)]
+    digest: Option<String>,
+}
+
+/// Fetches `version` (or the latest release when None) from the TES3MP GitHub
+/// releases, keeping only the asset built for `platform`. Prereleases count as
+/// the latest release on every channel but stable.
+pub async fn fetch_github_release(
+    client: &reqwest::Client,
+    platform: Platform,
+    version: Option<&str>,
+    channel: Channel,
+) -> Result<ReleaseInfo, String> {
+    // `/latest` skips prereleases, the newest entry of the full list doesn't
+    let newest_of_list = version.is_none() && channel != Channel::Stable;
+    let url = match version {
+        Some(version) => format!("{}/tags/tes3mp-{}", GITHUB_RELEASES_API, version),
+        None if newest_of_list => format!("{}?per_page=1", GITHUB_RELEASES_API),
+        None => format!("{}/latest", GITHUB_RELEASES_API),
+    };
+    log::info!("Fetching TES3MP release from GitHub: {}", url);
+
+    let response = client
+        .get(&url)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to GitHub: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "GitHub release request returned status: {}",
+            response.status()
+        ));
+    }
+    let release = if newest_of_list {
+        response
+            .json::<Vec<GithubRelease>>()
+            .await
+            .map_err(|e| format!("Failed to parse GitHub releases: {}", e))?
+            .into_iter()
+            .next()
+            .ok_or("GitHub has no TES3MP releases".to_string())?
+    } else {
+        response
+            .json::<GithubRelease>()
+            .await
+            .map_err(|e| format!("Failed to parse GitHub release: {}", e))?
+    };
+
+    let version = release
+        .tag_name
+        .trim_start_matches("tes3mp-")
+        .trim_start_matches('v')
+        .to_string();
+    let mut assets: Vec<GithubAsset> = release
+        .assets
+        .into_iter()
+        .filter(|asset| is_platform_asset(&asset.name, platform))
+        .collect();
+    // Installing whichever one GitHub lists first could pick the wrong build
+    if assets.len() > 1 {
+        let names: Vec<&str> = assets.iter().map(|asset| asset.name.as_str()).collect();
+        return Err(format!(
+            "GitHub release {} has more than one asset for {}: {}",
+            release.tag_name,
+            platform.as_str(),
+            names.join(", ")
+        ));
+    }
+    let asset = assets.pop().ok_or(format!(
+        "GitHub release {} has no asset for {}",
+        release.tag_name,
+        platform.as_str()
+    ))?;
+
+    let sha256 = asset_checksum(&asset);
+
+    Ok(ReleaseInfo {
+        version,
+        url: None,
+        mirrors: Vec::new(),
+        sha256: None,
+        signature: None,
+        notes: release.body,
+        release_date: release.published_at,
+        breaking: false,
+        assets: vec![ReleaseAsset {
+            platform,
+            url: asset.browser_download_url,
+            mirrors: Vec::new(),
+            sha256,
+            signature: None,
+            size: Some(asset.size),
+        }],
+    })
+}
+
+// The digest GitHub recorded for `asset`, or else the pinned one
+fn asset_checksum(asset: &GithubAsset) -> Option<String> {
+    if let Some(sha256) = asset
+        .digest
+        .as_deref()
+        .and_then(|digest| digest.strip_prefix("sha256:"))
+    {
+        return Some(sha256.to_string());
+    }
+
+    let pinned = PINNED_CHECKSUMS
+        .iter()
+        .find(|(name, _)| *name == asset.name)
+        .map(|(_, sha256)| sha256.to_string());
+    if pinned.is_none() {
+        log::warn!("No checksum known for GitHub release asset {}", asset.name);
+    }
+    pinned
+}
+
+// Release assets are named like `tes3mp.Win64.release.0.8.1.zip` and
+// `tes3mp-GNU+Linux-x86_64-release-0.8.1-<hash>.tar.gz`. The server-only
+// builds (`tes3mp-server-...`) lack the client and aren't installable.
+fn is_platform_asset(name: &str, platform: Platform) -> bool {
+    let name = name.to_lowercase();
+    if name.starts_with("tes3mp-server") {
+        return false;
+    }
+    match platform {
+        Platform::Windows => name.contains("win64") && name.ends_with(".zip"),
+        Platform::Linux => {
+            name.contains("linux")
+                && (name.contains("x86_64") || name.contains("amd64"))
+                && name.ends_with(".tar.gz")
+        }
+    }
+}
@@ -0,0 +1,792 @@
+use crate::api::ApiClient;
+use crate::archive::{extract_archive, ArchiveKind};
+use crate::config::{current_channel, get_nerevar_config, save_nerevar_config};
+use crate::download::{download_to_file, partial_path};
+use crate::github::fetch_github_release;
+use crate::integrity::{sha256_file, verify_archive};
+use crate::manifest::{build_manifest, check_install, read_manifest, write_manifest};
+use crate::operations::check_cancelled;
+use crate::platform::set_executable_permissions;
+use crate::preflight::{check_before_download, check_before_extract};
+use crate::reset::create_backup;
+use crate::staging::{stage_dir, DirSwap};
+use crate::types::{
+    Channel, InstallError, InstallMetadata, ManifestSource, Mode, NerevarConfig, Platform,
+    ReleaseAsset, ReleaseInfo, UpgradeReport, VersionComparison,
+};
+use crate::upgrade::{carry_over_user_data, snapshot_defaults};
+use crate::utils::find_tes3mp_folder;
+use crate::version_compare::{compare_versions, parse_version};
+use crate::versions::{
+    read_install_metadata, remove_install_metadata, version_install_dir, versions_dir,
+    write_install_metadata,
+};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+use tokio_util::sync::CancellationToken;
+
+pub async fn install_latest_release(
+    app_handle: &tauri::AppHandle,
+    cancel: &CancellationToken,
+) -> Result<String, InstallError> {
+    let report = install_release(app_handle, false, false, cancel).await?;
+    Ok(format!(
+        "TES3MP successfully installed to: {}",
+        report.tes3mp_path
+    ))
+}
+
+/// Installs the latest release like [`install_latest_release`], but carries
+/// server data, custom scripts and config edits over from the current install.
+///
+/// If the release channel's latest is older than the installed version this
+/// fails with [`InstallError::DowngradeRequired`], unless `allow_downgrade` is
+/// set; the user data is then backed up before it is carried over.
+pub async fn upgrade_latest_release(
+    app_handle: &tauri::AppHandle,
+    allow_downgrade: bool,
+    cancel: &CancellationToken,
+) -> Result<UpgradeReport, InstallError> {
+    install_release(app_handle, true, allow_downgrade, cancel).await
+}
+
+async fn install_release(
+    app_handle: &tauri::AppHandle,
+    upgrade: bool,
+    allow_downgrade: bool,
+    cancel: &CancellationToken,
+) -> Result<UpgradeReport, InstallError> {
+    let platform = Platform::current();
+
+    log::info!("Installing TES3MP for platform: {}", platform.as_str());
+
+    let api = app_handle.state::<ApiClient>();
+    let release = fetch_tes3mp_release(&api, platform, None).await?;
+    check_downgrade(&release.version, allow_downgrade)?;
+
+    let release = download_fetched_release(app_handle, release, platform, cancel).await?;
+
+    let report = install_archive(
+        &release.archive_path,
+        release.kind,
+        platform,
+        ArchiveVersion::Known(release.version),
+        upgrade,
+        true,
+        cancel,
+    )?;
+    record_install_source(&report.version, &release.source);
+    Ok(report)
+}
+
+/// A release archive that has been downloaded and verified.
+pub struct DownloadedRelease {
+    pub archive_path: PathBuf,
+    pub kind: ArchiveKind,
+    pub version: String,
+    // URL the archive was downloaded from
+    pub source: String,
+}
+
+// One release archive being fetched, tried against each of its sources in turn
+struct ReleaseDownload<'a> {
+    app_handle: &'a tauri::AppHandle,
+    client: &'a reqwest::Client,
+    archive_path: &'a Path,
+    sha256: &'a str,
+    signature: Option<&'a str>,
+    cancel: &'a CancellationToken,
+}
+
+impl ReleaseDownload<'_> {
+    // Downloads the archive from `url` and verifies it, leaving nothing behind on failure
+    async fn try_source(&self, url: &str) -> Result<(), String> {
+        if let Err(e) = download_to_file(
+            self.app_handle,
+            self.client,
+            url,
+            self.archive_path,
+            "tes3mp-download-progress",
+            self.cancel,
+        )
+        .await
+        {
+            // Don't resume another source from whatever this one sent
+            let _ = fs::remove_file(partial_path(self.archive_path));
+            return Err(e);
+        }
+
+        if let Err(e) = verify_archive(self.archive_path, self.sha256, self.signature) {
+            log::error!("{}", e);
+            if let Err(remove_err) = fs::remove_file(self.archive_path) {
+                log::error!("Failed to remove rejected archive: {}", remove_err);
+            }
+            return Err(format!("Downloaded TES3MP archive was rejected: {}", e));
+        }
+        Ok(())
+    }
+}
+
+// The asset's URL followed by its mirrors, without duplicates
+fn release_sources(asset: &ReleaseAsset) -> Vec<String> {
+    let mut sources = vec![asset.url.clone()];
+    for mirror in &asset.mirrors {
+        if !mirror.is_empty() && !sources.contains(mirror) {
+            sources.push(mirror.clone());
+        }
+    }
+    sources
+}
+
+// The GitHub download URL of `version`, unless it was already one of `sources`
+async fn github_fallback(
+    client: &reqwest::Client,
+    platform: Platform,
+    version: &str,
+    sources: &[String],
+) -> Option<String> {
+    let asset = fetch_github_release(client, platform, Some(version), Channel::Stable)
+        .await
+        .and_then(|release| select_asset(&release, platform));
+    match asset {
+        Ok(asset) if !sources.contains(&asset.url) => Some(asset.url),
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("No GitHub fallback for TES3MP {}: {}", version, e);
+            None
+        }
+    }
+}
+
+/// Downloads the release archive of `version` for `platform`, or of the latest
+/// release when `version` is None, and checks it against the published checksum.
+pub async fn download_release(
+    app_handle: &tauri::AppHandle,
+    platform: Platform,
+    version: Option<&str>,
+    cancel: &CancellationToken,
+) -> Result<DownloadedRelease, InstallError> {
+    //Step 0: get release info from nerevar-api which returns a body json { url, version, sha256, signature?, assets? }
+    let api = app_handle.state::<ApiClient>();
+    let release = fetch_tes3mp_release(&api, platform, version).await?;
+    download_fetched_release(app_handle, release, platform, cancel).await
+}
+
+// Downloads and verifies the archive of a release already fetched from the API
+async fn download_fetched_release(
+    app_handle: &tauri::AppHandle,
+    release: ReleaseInfo,
+    platform: Platform,
+    cancel: &CancellationToken,
+) -> Result<DownloadedRelease, InstallError> {
+    // Use temp directory to avoid Tauri rebuilds
+    let temp_dir = std::env::temp_dir();
+    log::info!("Using temp directory: {}", temp_dir.display());
+
+    let api = app_handle.state::<ApiClient>();
+    let version = release.version.clone();
+    let asset = select_asset(&release, platform)?;
+    log::info!("Release url: {}", asset.url);
+
+    let kind = ArchiveKind::from_name(&asset.url).unwrap_or(match platform {
+        Platform::Windows => ArchiveKind::Zip,
+        Platform::Linux => ArchiveKind::TarGz,
+    });
+
+    // Name the archive after the version so a partial download is never resumed
+    // against a different release
+    let archive_path = temp_dir.join(format!("tes3mp_{}.{}", version, kind.extension()));
+    log::info!("Archive will be saved to: {}", archive_path.display());
+
+    // Step 0.5: Fail early if the download can't fit or the folders can't be written
+    check_before_download(api.http(), &asset.url, &archive_path, &versions_dir()?).await?;
+
+    // Step 1-2.5: Download from the first source that works: the release URL,
+    // its mirrors, then the same release on GitHub. Each download is checked
+    // against the published checksum, and the existing installation is not
+    // touched until after this point.
+    let sha256 = asset
+        .sha256
+        .as_deref()
+        .ok_or_else(|| InstallError::ChecksumUnavailable {
+            version: version.clone(),
+            url: asset.url.clone(),
+        })?;
+    let download = ReleaseDownload {
+        app_handle,
+        client: api.http(),
+        archive_path: &archive_path,
+        sha256,
+        signature: asset.signature.as_deref(),
+        cancel,
+    };
+
+    let mut sources = release_sources(&asset);
+    let mut failures = Vec::new();
+    let mut github_checked = false;
+    let mut next = 0;
+    let source = loop {
+        if next == sources.len() && !github_checked {
+            github_checked = true;
+            if let Some(url) = github_fallback(api.http(), platform, &version, &sources).await {
+                sources.push(url);
+            }
+        }
+        let Some(url) = sources.get(next) else {
+            return Err(format!(
+                "Failed to download TES3MP {} from any source:\n{}",
+                version,
+                failures.join("\n")
+            )
+            .into());
+        };
+        next += 1;
+
+        log::info!(
+            "Downloading TES3MP from source {}/{}: {}",
+            next,
+            sources.len(),
+            url
+        );
+        match download.try_source(url).await {
+            Ok(()) => break url.clone(),
+            Err(e) if cancel.is_cancelled() => return Err(e.into()),
+            Err(e) => {
+                log::warn!("Download from {} failed: {}", url, e);
+                failures.push(format!("{}: {}", url, e));
+            }
+        }
+    };
+    log::info!(
+        "Archive saved as: {} (from {})",
+        archive_path.display(),
+        source
+    );
+
+    Ok(DownloadedRelease {
+        archive_path,
+        kind,
+        version,
+        source,
+    })
+}
+
+/// Installs TES3MP from an archive already on disk, e.g. for machines without
+/// internet access. The archive is left where it is.
+///
+/// The version is `version` if given, otherwise it is read from a version file
+/// or the folder name inside the archive, or the archive's own file name.
+pub fn install_local_archive(
+    archive_path: &Path,
+    version: Option<String>,
+    upgrade: bool,
+    cancel: &CancellationToken,
+) -> Result<UpgradeReport, InstallError> {
+    let platform = Platform::current();
+
+    if !archive_path.is_file() {
+        return Err(format!("TES3MP archive not found at: {}", archive_path.display()).into());
+    }
+
+    let file_name = archive_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let kind = ArchiveKind::from_name(&file_name).ok_or(format!(
+        "Unsupported archive type: {} (expected .zip or .tar.gz)",
+        file_name
+    ))?;
+
+    log::info!(
+        "Installing TES3MP for platform {} from local archive: {}",
+        platform.as_str(),
+        archive_path.display()
+    );
+
+    // There is no published checksum to check a local archive against, but log
+    // its hash so it can be compared by hand
+    log::info!("Local archive SHA-256: {}", sha256_file(archive_path)?);
+
+    let report = install_archive(
+        archive_path,
+        kind,
+        platform,
+        version.map_or(ArchiveVersion::Detect, ArchiveVersion::Known),
+        upgrade,
+        false,
+        cancel,
+    )?;
+    record_install_source(&report.version, &archive_path.to_string_lossy());
+    Ok(report)
+}
+
+/// What [`install_archive`] knows about the version of an archive.
+pub enum ArchiveVersion {
+    // From the release info or the user, taken as is
+    Known(String),
+    // Read from the archive
+    Detect,
+    // Has to be this version, which is checked before anything is installed
+    Expected(String),
+}
+
+/// Steps shared by downloaded and local archives: extract, validate, move into
+/// the versions directory and make it the active install.
+///
+/// Cancelling `cancel` stops the install before anything is moved into place.
+pub fn install_archive(
+    archive_path: &Path,
+    kind: ArchiveKind,
+    platform: Platform,
+    version: ArchiveVersion,
+    upgrade: bool,
+    delete_archive: bool,
+    cancel: &CancellationToken,
+) -> Result<UpgradeReport, InstallError> {
+    let extract_dir = "tes3mp_extracted";
+    let extract_path = std::env::temp_dir().join(extract_dir);
+
+    let prepared = prepare_release(
+        archive_path,
+        kind,
+        platform,
+        version,
+        delete_archive,
+        &extract_path,
+        cancel,
+    );
+    let (tes3mp_folder, version) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            // Nothing has been installed yet, only the temp files need to go
+            remove_temp_dir(&extract_path);
+            if cancel.is_cancelled() && delete_archive && archive_path.exists() {
+                if let Err(remove_err) = fs::remove_file(archive_path) {
+                    log::warn!("Failed to delete archive: {}", remove_err);
+                }
+            }
+            return Err(e);
+        }
+    };
+
+    // Step 6.5: Record the pristine defaults and a manifest of the release files
+    // before anything is merged into them
+    let existing_config = get_nerevar_config()?;
+    let install_path = version_install_dir(&version)?;
+
+    snapshot_defaults(&tes3mp_folder, &version)?;
+    write_manifest(&build_manifest(&tes3mp_folder, &version, platform)?)?;
+
+    // Step 7: Stage the new install next to its versioned AppData location, so
+    // the swap below is a rename on the same filesystem
+
+    log::info!(
+        "Moving TES3MP to permanent location: {}",
+        install_path.display()
+    );
+    let staging_path = stage_dir(&tes3mp_folder, &install_path)?;
+
+    // Step 8: Clean up temp extraction directory
+    remove_temp_dir(&extract_path);
+
+    // Carry user data over from the active install when upgrading
+    let carried_over = match existing_config.as_ref() {
+        Some(existing) if upgrade => carry_over_user_data(
+            Path::new(&existing.tes3mp_path),
+            &staging_path,
+            Some(existing.version.as_str()),
+        ),
+        _ => Ok((Vec::new(), Vec::new())),
+    };
+    let (preserved, conflicts) = match carried_over {
+        Ok(carried_over) => carried_over,
+        Err(e) => {
+            discard_staging(&staging_path);
+            return Err(e.into());
+        }
+    };
+
+    // Step 9: Swap the staged install in, then record it and make it the active
+    // version. Until this succeeds the previous install and config are kept, and
+    // they are put back if recording fails.
+    let previous_metadata = read_install_metadata(&version)?;
+    let swap = match DirSwap::swap_in(&staging_path, &install_path) {
+        Ok(swap) => swap,
+        Err(e) => {
+            discard_staging(&staging_path);
+            return Err(e.into());
+        }
+    };
+
+    let tes3mp_path = install_path.to_string_lossy().to_string();
+    if let Err(e) = record_install(&version, &tes3mp_path, platform, existing_config.clone()) {
+        log::error!("Failed to record TES3MP {}: {}", version, e);
+        if let Err(rollback_err) = swap.rollback() {
+            log::error!("{}", rollback_err);
+        }
+        let restored_metadata = match &previous_metadata {
+            Some(metadata) => write_install_metadata(metadata),
+            None => remove_install_metadata(&version),
+        };
+        if let Err(restore_err) = restored_metadata {
+            log::error!("Failed to restore install metadata: {}", restore_err);
+        }
+        if let Some(config) = &existing_config {
+            if let Err(restore_err) = save_nerevar_config(config) {
+                log::error!("Failed to restore Nerevar config: {}", restore_err);
+            }
+        }
+        return Err(format!(
+            "Failed to install TES3MP {}, the previous install was restored: {}",
+            version, e
+        )
+        .into());
+    }
+    swap.commit();
+
+    Ok(UpgradeReport {
+        version,
+        tes3mp_path,
+        preserved,
+        conflicts,
+    })
+}
+
+// Steps up to the point the release is ready to install: extract it to
+// `extract_path`, find the TES3MP folder in it and work out its version
+fn prepare_release(
+    archive_path: &Path,
+    kind: ArchiveKind,
+    platform: Platform,
+    version: ArchiveVersion,
+    delete_archive: bool,
+    extract_path: &Path,
+    cancel: &CancellationToken,
+) -> Result<(PathBuf, String), InstallError> {
+    // Step 2.9: Make sure there is room for the extracted files in the temp
+    // directory and in the versions directory before unpacking anything
+    check_before_extract(archive_path, kind, &versions_dir()?)?;
+
+    // Step 3: Extract the archive
+    if extract_path.exists() {
+        log::info!(
+            "Removing existing extraction directory: {}",
+            extract_path.display()
+        );
+        fs::remove_dir_all(extract_path)
+            .map_err(|e| format!("Failed to remove existing extraction directory: {}", e))?;
+    }
+
+    log::info!("Extracting archive to: {}", extract_path.display());
+    extract_archive(archive_path, kind, extract_path, cancel)?;
+
+    log::info!("Archive extracted to: {}", extract_path.display());
+
+    // Step 4: Delete the downloaded archive
+    if delete_archive {
+        fs::remove_file(archive_path).map_err(|e| format!("Failed to delete archive: {}", e))?;
+
+        log::info!("Archive deleted: {}", archive_path.display());
+    }
+
+    // Step 5-6: Find the TES3MP folder and make sure the client binary is in it
+    let tes3mp_folder = locate_tes3mp_folder(extract_path, platform)?;
+    set_executable_permissions(&tes3mp_folder)?;
+
+    let version = match version {
+        ArchiveVersion::Known(version) => version,
+        ArchiveVersion::Detect => detect_version(&tes3mp_folder, archive_path)?,
+        ArchiveVersion::Expected(version) => {
+            confirm_version(&tes3mp_folder, archive_path, &version)?;
+            version
+        }
+    };
+    log::info!("Installing TES3MP version: {}", version);
+
+    // Last point the install can be cancelled, from here on it is being moved into place
+    check_cancelled(cancel)?;
+    Ok((tes3mp_folder, version))
+}
+
+fn remove_temp_dir(extract_path: &Path) {
+    if extract_path.exists() {
+        log::info!(
+            "Cleaning up temp extraction directory: {}",
+            extract_path.display()
+        );
+        if let Err(e) = fs::remove_dir_all(extract_path) {
+            log::warn!("Failed to clean up temp extraction directory: {}", e);
+        }
+    }
+}
+
+// Writes the install metadata and makes `version` the active install, keeping
+// the mode, pins and the version to roll back to
+fn record_install(
+    version: &str,
+    tes3mp_path: &str,
+    platform: Platform,
+    existing_config: Option<NerevarConfig>,
+) -> Result<(), String> {
+    let installed_at = chrono::Utc::now().to_rfc3339();
+    write_install_metadata(&InstallMetadata {
+        version: version.to_string(),
+        tes3mp_path: tes3mp_path.to_string(),
+        platform: Some(platform),
+        installed_at: Some(installed_at.clone()),
+        source: None,
+    })?;
+
+    let mut config = existing_config.unwrap_or_default();
+    if !config.version.is_empty() && config.version != version {
+        config.previous_version = Some(config.version.clone());
+    }
+    config.tes3mp_path = tes3mp_path.to_string();
+    config.version = version.to_string();
+    config.last_updated = installed_at;
+    config.mode = config.mode.or(Some(Mode::Player));
+    config.platform = Some(platform);
+    save_nerevar_config(&config)
+}
+
+/// Notes where an installed version came from in its metadata. Only logged on
+/// failure, the install itself already succeeded.
+pub fn record_install_source(version: &str, source: &str) {
+    let result = read_install_metadata(version).and_then(|metadata| match metadata {
+        Some(mut metadata) => {
+            metadata.source = Some(source.to_string());
+            write_install_metadata(&metadata)
+        }
+        None => Ok(()),
+    });
+    if let Err(e) = result {
+        log::warn!(
+            "Failed to record install source of TES3MP {}: {}",
+            version,
+            e
+        );
+    }
+}
+
+// Refuses to replace the active install with an older release unless allowed,
+// and backs up the user data first when it is
+fn check_downgrade(latest: &str, allow_downgrade: bool) -> Result<(), InstallError> {
+    let Some(config) = get_nerevar_config()? else {
+        return Ok(());
+    };
+    if compare_versions(&config.version, latest) != VersionComparison::Older {
+        return Ok(());
+    }
+
+    if !allow_downgrade {
+        return Err(InstallError::DowngradeRequired {
+            installed: config.version,
+            available: latest.to_string(),
+            channel: config.channel,
+        });
+    }
+
+    log::warn!(
+        "Downgrading TES3MP from {} to {} ({} channel)",
+        config.version,
+        latest,
+        config.channel.as_str()
+    );
+    let backup_path = create_backup("downgrade")?;
+    log::info!(
+        "Backed up TES3MP data before downgrading: {}",
+        backup_path.display()
+    );
+    Ok(())
+}
+
+fn discard_staging(staging_path: &Path) {
+    if let Err(e) = fs::remove_dir_all(staging_path) {
+        log::warn!(
+            "Failed to remove staging directory {}: {}",
+            staging_path.display(),
+            e
+        );
+    }
+}
+
+/// Fetches the release info of `version`, or of the latest release on the
+/// configured channel when None.
+pub async fn fetch_tes3mp_release(
+    api: &ApiClient,
+    platform: Platform,
+    version: Option<&str>,
+) -> Result<ReleaseInfo, String> {
+    let channel = current_channel();
+    let mut query = vec![("platform", platform.as_str())];
+    match version {
+        Some(version) => query.push(("version", version)),
+        None => query.push(("channel", channel.as_str())),
+    }
+
+    let release = match api
+        .get_json::<ReleaseInfo>("/releases/tes3mp", &query)
+        .await
+    {
+        Ok(release) => release,
+        Err(e) => {
+            log::warn!(
+                "Nerevar API unavailable ({}), falling back to GitHub releases",
+                e
+            );
+            fetch_github_release(api.http(), platform, version, channel)
+                .await
+                .map_err(|github_err| {
+                    format!(
+                        "Failed to get TES3MP release from the Nerevar API ({}) or GitHub ({})",
+                        e, github_err
+                    )
+                })?
+        }
+    };
+
+    if let Some(version) = version {
+        if release.version != version {
+            return Err(format!(
+                "Release endpoint returned TES3MP {} instead of {}",
+                release.version, version
+            ));
+        }
+    }
+
+    log::info!("Successfully retrieved release: {}", release.version);
+    Ok(release)
+}
+
+/// Picks the release asset built for `platform`.
+///
+/// Falls back to the top-level `url`/`sha256`, which is what the endpoint
+/// returned before it listed per-platform assets.
+pub fn select_asset(release: &ReleaseInfo, platform: Platform) -> Result<ReleaseAsset, String> {
+    if let Some(asset) = release.assets.iter().find(|a| a.platform == platform) {
+        return Ok(asset.clone());
+    }
+
+    match &release.url {
+        Some(url) => Ok(ReleaseAsset {
+            platform,
+            url: url.clone(),
+            mirrors: release.mirrors.clone(),
+            sha256: release.sha256.clone(),
+            signature: release.signature.clone(),
+            size: None,
+        }),
+        None => Err(format!(
+            "TES3MP {} has no release for {}",
+            release.version,
+            platform.as_str()
+        )),
+    }
+}
+
+/// Finds the TES3MP folder inside `extract_path` and checks that the client
+/// binary for `platform` is there.
+pub fn locate_tes3mp_folder(extract_path: &Path, platform: Platform) -> Result<PathBuf, String> {
+    let client_exe = platform.exe_name("tes3mp");
+
+    log::info!("Looking for {} in: {}", client_exe, extract_path.display());
+    let tes3mp_folder = find_tes3mp_folder(extract_path, platform)?;
+    log::info!("Found TES3MP folder: {}", tes3mp_folder.display());
+
+    let exe_path = tes3mp_folder.join(&client_exe);
+    if !exe_path.exists() {
+        // List all files in the folder for debugging
+        log::error!("{} not found in: {}", client_exe, tes3mp_folder.display());
+        if let Ok(entries) = fs::read_dir(&tes3mp_folder) {
+            log::info!("Files in TES3MP folder:");
+            for entry in entries.flatten() {
+                log::info!("  - {}", entry.file_name().to_string_lossy());
+            }
+        }
+        return Err(format!(
+            "{} not found in: {}",
+            client_exe,
+            tes3mp_folder.display()
+        ));
+    }
+
+    log::info!("Found {} at: {}", client_exe, exe_path.display());
+    log::info!(
+        "TES3MP installation verified in: {}",
+        tes3mp_folder.display()
+    );
+    Ok(tes3mp_folder)
+}
+
+// Files TES3MP builds have used to record their version
+const VERSION_FILES: [&str; 3] = ["tes3mp-version.txt", "version.txt", "VERSION"];
+
+/// Works out the TES3MP version of an extracted archive that didn't come with
+/// release info: a version file in the TES3MP folder, then the folder's name,
+/// then the archive's file name (e.g. `tes3mp.Win64.release.0.8.1.zip`).
+fn detect_version(tes3mp_folder: &Path, archive_path: &Path) -> Result<String, String> {
+    for file_name in VERSION_FILES {
+        let path = tes3mp_folder.join(file_name);
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if let Some(version) = content.lines().find_map(find_version) {
+            log::info!("Read TES3MP version {} from: {}", version, path.display());
+            return Ok(version);
+        }
+    }
+
+    let candidates = [tes3mp_folder.file_name(), archive_path.file_name()];
+    for name in candidates.into_iter().flatten() {
+        if let Some(version) = find_version(&name.to_string_lossy()) {
+            log::info!("Read TES3MP version {} from name: {:?}", version, name);
+            return Ok(version);
+        }
+    }
+
+    Err(
+        "Could not determine the TES3MP version of this archive. Please enter it manually."
+            .to_string(),
+    )
+}
+
+// Checks that the extracted release is `version`: against the manifest recorded
+// when it was installed, or else by the version found in the archive
+fn confirm_version(tes3mp_folder: &Path, archive_path: &Path, version: &str) -> Result<(), String> {
+    if let Some(manifest) = read_manifest(version)? {
+        let report = check_install(tes3mp_folder, &manifest, ManifestSource::Local)?;
+        if report.missing.is_empty() && report.modified.is_empty() {
+            return Ok(());
+        }
+        return Err(format!(
+            "This archive doesn't match the files of TES3MP {} ({} missing, {} different)",
+            version,
+            report.missing.len(),
+            report.modified.len()
+        ));
+    }
+
+    let detected = detect_version(tes3mp_folder, archive_path)
+        .map_err(|_| format!("Could not confirm that this archive is TES3MP {}", version))?;
+    if compare_versions(&detected, version) != VersionComparison::Equal {
+        return Err(format!(
+            "This archive contains TES3MP {}, not {}",
+            detected, version
+        ));
+    }
+    Ok(())
+}
+
+// First `major.minor[.patch][-prerelease]` in `text`, normalized through semver
+fn find_version(text: &str) -> Option<String> {
+    // Prereleases have to start with a letter so build hashes after the version
+    // in Linux tarball names aren't taken for one
+    let version_regex =
+        Regex::new(r"\d+\.\d+(?:\.\d+)?(?:-[A-Za-z][0-9A-Za-z]*(?:\.[0-9A-Za-z]+)*)?").ok()?;
+    let version = version_regex
+        .find_iter(text)
+        .find_map(|m| parse_version(m.as_str()))?;
+    Some(version.to_string())
+}
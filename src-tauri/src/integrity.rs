@@ -0,0 +1,124 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+// Base64 ed25519 public key of the Nerevar release key, baked in at build time.
+// Release builds set NEREVAR_RELEASE_PUBLIC_KEY; dev builds may leave it unset.
+pub const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("NEREVAR_RELEASE_PUBLIC_KEY");
+
+/// Hex encoded SHA-256 digest of the file at `path`.
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file for hashing: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read file for hashing: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Checks a downloaded archive against the SHA-256 published by the release
+/// endpoint and, when one is published, its detached signature.
+///
+/// The signature is an ed25519 signature over the raw 32-byte SHA-256 digest,
+/// base64 encoded, made with the Nerevar release key.
+pub fn verify_archive(
+    path: &Path,
+    expected_sha256: &str,
+    signature: Option<&str>,
+) -> Result<(), String> {
+    let actual = check_sha256(path, expected_sha256)?;
+
+    match (signature, RELEASE_PUBLIC_KEY) {
+        (Some(signature), Some(public_key)) => {
+            let digest = hex::decode(&actual)
+                .map_err(|e| format!("Failed to decode archive digest: {}", e))?;
+            verify_signature(&digest, signature, public_key)?;
+            log::info!("Release signature verified");
+        }
+        (Some(_), None) => {
+            log::warn!("Release is signed but this build has no release key embedded, skipping signature check");
+        }
+        (None, _) => {
+            log::info!("Release has no detached signature, relying on SHA-256 only");
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks a downloaded app update against its published SHA-256 and signature.
+///
+/// Unlike [`verify_archive`] the signature is required, and so is a release key
+/// in this build: an app update is an executable Nerevar swaps in for itself.
+pub fn verify_app_update(
+    path: &Path,
+    expected_sha256: Option<&str>,
+    signature: Option<&str>,
+) -> Result<(), String> {
+    let public_key = RELEASE_PUBLIC_KEY
+        .ok_or("This build has no release key embedded, so app updates can't be verified")?;
+    let expected_sha256 = expected_sha256
+        .ok_or("The update has no published SHA-256 checksum, refusing to install")?;
+    let signature = signature.ok_or("The update is not signed, refusing to install")?;
+
+    let actual = check_sha256(path, expected_sha256)?;
+    let digest =
+        hex::decode(&actual).map_err(|e| format!("Failed to decode update digest: {}", e))?;
+    verify_signature(&digest, signature, public_key)?;
+
+    log::info!("Update signature verified");
+    Ok(())
+}
+
+// Hashes the file at `path` and fails unless it matches `expected_sha256`
+fn check_sha256(path: &Path, expected_sha256: &str) -> Result<String, String> {
+    log::info!("Verifying SHA-256 of: {}", path.display());
+    let actual = sha256_file(path)?;
+    let expected = expected_sha256.trim().to_lowercase();
+
+    if actual != expected {
+        return Err(format!(
+            "Checksum mismatch for {}: expected {}, got {}. The file may be truncated or tampered with.",
+            path.display(),
+            expected,
+            actual
+        ));
+    }
+    log::info!("SHA-256 verified: {}", actual);
+    Ok(actual)
+}
+
+/// Verifies a base64 ed25519 `signature` over `message` with a base64 `public_key`.
+pub fn verify_signature(message: &[u8], signature: &str, public_key: &str) -> Result<(), String> {
+    let key_bytes: [u8; 32] = BASE64
+        .decode(public_key.trim())
+        .map_err(|e| format!("Invalid release public key: {}", e))?
+        .try_into()
+        .map_err(|_| "Invalid release public key: expected 32 bytes".to_string())?;
+    let key = VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|e| format!("Invalid release public key: {}", e))?;
+
+    let signature_bytes = BASE64
+        .decode(signature.trim())
+        .map_err(|e| format!("Invalid signature encoding: {}", e))?;
+    let signature =
+        Signature::from_slice(&signature_bytes).map_err(|e| format!("Invalid signature: {}", e))?;
+
+    key.verify(message, &signature).map_err(|_| {
+        "Signature verification failed: the file was not signed by the Nerevar release key"
+            .to_string()
+    })
+}
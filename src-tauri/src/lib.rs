@@ -1,24 +1,75 @@
 // Import our modules
+mod api;
+mod archive;
+mod changelog;
 mod commands;
 mod config;
+mod cron;
+mod download;
+mod github;
+mod installer;
+mod integrity;
+mod manifest;
+mod merge;
+mod operations;
 mod parsers;
+mod platform;
+mod preflight;
+mod process_stats;
+mod reset;
+mod scheduler;
+mod server;
+mod server_log;
+mod staging;
+mod supervisor;
 mod types;
+mod updater;
+mod upgrade;
 mod utils;
+mod version_compare;
+mod versions;
 
 // Re-export types for external use
 pub use types::*;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let api_url = "https://nerevar.cc/api/v1";
-    log::info!("Nerevar API URL: {}", api_url);
+    if updater::handle_version_flag() {
+        return;
+    }
+
+    let api_client = api::ApiClient::new().expect("failed to create Nerevar API client");
     tauri::Builder::default()
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_cli::init())
+        .manage(api_client)
+        .manage(operations::OperationRegistry::default())
+        .manage(updater::AppUpdates::default())
+        .manage(supervisor::ProcessSupervisor::default())
+        .manage(server_log::ServerLog::default())
+        .manage(server::ServerWatchdog::default())
+        .manage(scheduler::Scheduler::load())
+        .manage(process_stats::ProcessStatsHistory::default())
         .invoke_handler(tauri::generate_handler![
             commands::download_latest_windows_release,
+            commands::upgrade_tes3mp,
+            commands::install_tes3mp_from_archive,
+            commands::verify_tes3mp_install,
+            commands::repair_tes3mp_install,
+            commands::factory_reset_nerevar,
+            commands::factory_reset_tes3mp,
+            commands::cancel_operation,
+            commands::list_tes3mp_versions,
+            commands::activate_tes3mp_version,
+            commands::rollback_tes3mp_version,
+            commands::pin_tes3mp_version,
+            commands::unpin_tes3mp_version,
+            commands::remove_tes3mp_version,
             commands::get_nerevar_config,
+            commands::get_api_url,
+            commands::set_api_settings,
+            commands::set_release_channel,
             commands::get_openmw_config,
             commands::run_openmw_wizard,
             commands::run_openmw_launcher,
@@ -28,6 +79,7 @@ pub fn run() {
             commands::check_for_app_update,
             commands::download_app_update,
             commands::apply_app_update,
+            commands::rollback_app_update,
             commands::run_tes3mp_browser,
             commands::run_tes3mp,
             commands::ping_server_tcp,
@@ -37,6 +89,20 @@ pub fn run() {
             commands::get_tes3mp_server_settings,
             commands::set_tes3mp_server_settings,
             commands::run_tes3mp_server,
+            commands::get_schedules,
+            commands::set_schedules,
+            commands::run_scheduled_task,
+            commands::get_watchdog_status,
+            commands::set_watchdog_policy,
+            commands::reset_watchdog,
+            commands::get_server_log,
+            commands::clear_server_log,
+            commands::send_server_command,
+            commands::get_server_command_history,
+            commands::list_processes,
+            commands::get_process_stats,
+            commands::stop_process,
+            commands::kill_process,
             commands::open_config_lua_in_explorer,
             commands::open_nerevar_appdata_dir_in_explorer,
         ])
@@ -48,6 +114,13 @@ pub fn run() {
                         .build(),
                 )?;
             }
+            log::info!("Nerevar API URL: {}", api::resolve_api_url());
+            // Installs from before side-by-side versions live in <appdata>/TES3MP
+            if let Err(e) = versions::migrate_legacy_install() {
+                log::error!("Failed to migrate legacy TES3MP install: {}", e);
+            }
+            scheduler::start(app.handle().clone());
+            process_stats::start(app.handle().clone());
             Ok(())
         })
         .run(tauri::generate_context!())
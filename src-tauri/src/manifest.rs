@@ -0,0 +1,410 @@
+use crate::api::ApiClient;
+use crate::archive::{extract_archive, ArchiveKind};
+use crate::config::get_nerevar_config;
+use crate::installer::{download_release, locate_tes3mp_folder};
+use crate::integrity::sha256_file;
+use crate::platform::set_executable_permissions;
+use crate::types::{
+    InstallError, InstallManifest, IntegrityReport, ManifestEntry, ManifestSource, Platform,
+    RepairReport,
+};
+use crate::upgrade::is_user_data;
+use crate::versions::{read_install_metadata, validate_version_name, versions_dir};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tauri::Manager;
+use tokio_util::sync::CancellationToken;
+
+fn manifest_path(version: &str) -> Result<PathBuf, String> {
+    validate_version_name(version)?;
+    Ok(versions_dir()?.join(format!("{}.manifest.json", version)))
+}
+
+/// Records the size and hash of every release file in `tes3mp_folder`. User
+/// data and configs Nerevar edits are left out, they are expected to change.
+pub fn build_manifest(
+    tes3mp_folder: &Path,
+    version: &str,
+    platform: Platform,
+) -> Result<InstallManifest, String> {
+    let mut files = Vec::new();
+    for relative_path in collect_files(tes3mp_folder)? {
+        if is_user_data(&relative_path) {
+            continue;
+        }
+
+        let path = tes3mp_folder.join(&relative_path);
+        let size = fs::metadata(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+            .len();
+        files.push(ManifestEntry {
+            sha256: sha256_file(&path)?,
+            path: relative_path,
+            size,
+        });
+    }
+
+    log::info!(
+        "Built manifest of {} files for TES3MP {}",
+        files.len(),
+        version
+    );
+    Ok(InstallManifest {
+        version: version.to_string(),
+        platform: Some(platform),
+        files,
+    })
+}
+
+pub fn write_manifest(manifest: &InstallManifest) -> Result<(), String> {
+    let path = manifest_path(&manifest.version)?;
+    let json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    log::info!("Manifest written: {}", path.display());
+    Ok(())
+}
+
+pub fn read_manifest(version: &str) -> Result<Option<InstallManifest>, String> {
+    let path = manifest_path(version)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read manifest: {}", e))?;
+    let manifest =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse manifest: {}", e))?;
+    Ok(Some(manifest))
+}
+
+pub fn remove_manifest(version: &str) -> Result<(), String> {
+    let path = manifest_path(version)?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove manifest: {}", e))?;
+    }
+    Ok(())
+}
+
+// The manifest recorded at install time, or the one published for the release
+// (installs from before manifests were recorded)
+async fn load_manifest(
+    api: &ApiClient,
+    version: &str,
+    platform: Platform,
+) -> Result<(InstallManifest, ManifestSource), String> {
+    if let Some(manifest) = read_manifest(version)? {
+        return Ok((manifest, ManifestSource::Local));
+    }
+
+    log::info!(
+        "No recorded manifest for TES3MP {}, fetching it from the API",
+        version
+    );
+    let manifest = api
+        .get_json::<InstallManifest>(
+            "/releases/tes3mp/manifest",
+            &[("platform", platform.as_str()), ("version", version)],
+        )
+        .await
+        .map_err(|e| format!("Failed to get manifest for TES3MP {}: {}", version, e))?;
+
+    // It is cached under its own version, so a mismatched reply would replace
+    // the manifest of another install
+    if manifest.version != version {
+        return Err(format!(
+            "API returned the manifest of TES3MP {} when asked for {}",
+            manifest.version, version
+        ));
+    }
+    if let Some(other) = manifest.platform.filter(|p| *p != platform) {
+        return Err(format!(
+            "API returned the {} manifest when asked for {}",
+            other.as_str(),
+            platform.as_str()
+        ));
+    }
+
+    // Cache it so the next check works offline
+    write_manifest(&manifest)?;
+    Ok((manifest, ManifestSource::Api))
+}
+
+/// Compares the files in `tes3mp_folder` against `manifest`.
+pub fn check_install(
+    tes3mp_folder: &Path,
+    manifest: &InstallManifest,
+    manifest_source: ManifestSource,
+) -> Result<IntegrityReport, String> {
+    let mut missing = Vec::new();
+    let mut modified = Vec::new();
+    let mut checked = 0;
+
+    for entry in &manifest.files {
+        if is_user_data(&entry.path) {
+            continue;
+        }
+        let Some(relative_path) = safe_relative_path(&entry.path) else {
+            log::warn!("Skipping unsafe manifest path: {}", entry.path);
+            continue;
+        };
+        checked += 1;
+
+        let path = tes3mp_folder.join(relative_path);
+        let Ok(metadata) = fs::metadata(&path) else {
+            missing.push(entry.path.clone());
+            continue;
+        };
+        // Only hash files whose size matches, a different size is already a change
+        if metadata.len() != entry.size || !sha256_file(&path)?.eq_ignore_ascii_case(&entry.sha256)
+        {
+            modified.push(entry.path.clone());
+        }
+    }
+
+    let known: HashSet<&str> = manifest.files.iter().map(|e| e.path.as_str()).collect();
+    let extra: Vec<String> = collect_files(tes3mp_folder)?
+        .into_iter()
+        // Scripts users add next to the stock ones are theirs too
+        .filter(|path| {
+            !known.contains(path.as_str())
+                && !is_user_data(path)
+                && !path.starts_with("server/scripts/")
+        })
+        .collect();
+
+    for path in &missing {
+        log::warn!("Missing: {}", path);
+    }
+    for path in &modified {
+        log::warn!("Modified: {}", path);
+    }
+    for path in &extra {
+        log::info!("Extra: {}", path);
+    }
+
+    Ok(IntegrityReport {
+        version: manifest.version.clone(),
+        tes3mp_path: tes3mp_folder.to_string_lossy().to_string(),
+        manifest_source,
+        checked,
+        healthy: missing.is_empty() && modified.is_empty(),
+        missing,
+        modified,
+        extra,
+    })
+}
+
+// The install directory and version to check: `version` if given, otherwise the active one
+fn resolve_target(version: Option<String>) -> Result<(PathBuf, String, Platform), String> {
+    let config = get_nerevar_config()?
+        .ok_or("No Nerevar config found. Please install TES3MP first.".to_string())?;
+
+    let (install_dir, version) = match version {
+        Some(version) => (crate::versions::version_install_dir(&version)?, version),
+        None => (PathBuf::from(&config.tes3mp_path), config.version.clone()),
+    };
+    if !install_dir.is_dir() {
+        return Err(format!("TES3MP {} is not installed", version));
+    }
+
+    let platform = read_install_metadata(&version)?
+        .and_then(|m| m.platform)
+        .or(config.platform)
+        .unwrap_or(Platform::current());
+    Ok((install_dir, version, platform))
+}
+
+/// Checks an installed version (the active one if `version` is None) for
+/// missing, modified and extra files.
+pub async fn verify_installation(
+    app_handle: &tauri::AppHandle,
+    version: Option<String>,
+) -> Result<IntegrityReport, String> {
+    let (install_dir, version, platform) = resolve_target(version)?;
+    log::info!("Verifying TES3MP {} in: {}", version, install_dir.display());
+
+    let api = app_handle.state::<ApiClient>();
+    let (manifest, source) = load_manifest(&api, &version, platform).await?;
+    check_install(&install_dir, &manifest, source)
+}
+
+/// Replaces the missing and modified files of an installed version with the
+/// ones from its release archive. Extra files and user data are left alone.
+///
+/// The archive is downloaded unless `archive_path` points at a local copy.
+pub async fn repair_installation(
+    app_handle: &tauri::AppHandle,
+    version: Option<String>,
+    archive_path: Option<String>,
+    cancel: &CancellationToken,
+) -> Result<RepairReport, InstallError> {
+    let (install_dir, version, platform) = resolve_target(version)?;
+
+    let api = app_handle.state::<ApiClient>();
+    let (manifest, source) = load_manifest(&api, &version, platform).await?;
+    let report = check_install(&install_dir, &manifest, source)?;
+    if report.healthy {
+        log::info!("TES3MP {} has no broken files to repair", version);
+        return Ok(RepairReport {
+            version,
+            repaired: Vec::new(),
+            report,
+        });
+    }
+
+    let broken: Vec<String> = report
+        .missing
+        .iter()
+        .chain(report.modified.iter())
+        .cloned()
+        .collect();
+    log::info!("Repairing {} files of TES3MP {}", broken.len(), version);
+
+    // Get the release archive and extract it to a temp directory
+    let (archive, kind, downloaded) = match archive_path {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            let kind = ArchiveKind::from_name(&path.to_string_lossy())
+                .ok_or("Unsupported archive type (expected .zip or .tar.gz)".to_string())?;
+            (path, kind, false)
+        }
+        None => {
+            let release = download_release(app_handle, platform, Some(&version), cancel).await?;
+            (release.archive_path, release.kind, true)
+        }
+    };
+
+    let extract_path = std::env::temp_dir().join("tes3mp_repair");
+    if extract_path.exists() {
+        fs::remove_dir_all(&extract_path)
+            .map_err(|e| format!("Failed to remove existing repair directory: {}", e))?;
+    }
+    let result = extract_archive(&archive, kind, &extract_path, cancel)
+        .and_then(|_| locate_tes3mp_folder(&extract_path, platform))
+        .and_then(|release_folder| {
+            replace_files(&release_folder, &install_dir, &manifest, &broken)
+        });
+
+    if downloaded {
+        if let Err(e) = fs::remove_file(&archive) {
+            log::warn!("Failed to delete repair archive: {}", e);
+        }
+    }
+    if let Err(e) = fs::remove_dir_all(&extract_path) {
+        log::warn!("Failed to clean up repair directory: {}", e);
+    }
+
+    let repaired = result?;
+    set_executable_permissions(&install_dir)?;
+
+    let report = check_install(&install_dir, &manifest, source)?;
+    log::info!(
+        "Repaired {} files of TES3MP {}, healthy: {}",
+        repaired.len(),
+        version,
+        report.healthy
+    );
+    Ok(RepairReport {
+        version,
+        repaired,
+        report,
+    })
+}
+
+// Copies `paths` from the extracted release into the install, but only when the
+// release copy matches the manifest
+fn replace_files(
+    release_folder: &Path,
+    install_dir: &Path,
+    manifest: &InstallManifest,
+    paths: &[String],
+) -> Result<Vec<String>, String> {
+    let entries: HashMap<&str, &ManifestEntry> = manifest
+        .files
+        .iter()
+        .map(|e| (e.path.as_str(), e))
+        .collect();
+    let mut repaired = Vec::new();
+
+    for path in paths {
+        let (Some(entry), Some(relative_path)) =
+            (entries.get(path.as_str()), safe_relative_path(path))
+        else {
+            continue;
+        };
+
+        let source = release_folder.join(&relative_path);
+        if !source.is_file() {
+            return Err(format!("Release archive does not contain: {}", path));
+        }
+        if !sha256_file(&source)?.eq_ignore_ascii_case(&entry.sha256) {
+            return Err(format!(
+                "{} in the release archive does not match the manifest",
+                path
+            ));
+        }
+
+        let target = install_dir.join(&relative_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::copy(&source, &target).map_err(|e| format!("Failed to replace {}: {}", path, e))?;
+
+        log::info!("Repaired: {}", path);
+        repaired.push(path.clone());
+    }
+
+    Ok(repaired)
+}
+
+// Manifest paths come from the API too, so only accept plain relative paths
+fn safe_relative_path(path: &str) -> Option<PathBuf> {
+    let relative_path = PathBuf::from(path);
+    if relative_path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        Some(relative_path)
+    } else {
+        None
+    }
+}
+
+// Every file under `root`, as `/`-separated paths relative to it. Symlinks are
+// left out, not followed: what they point to is listed on its own, and a link
+// that dangles or loops can't be hashed.
+fn collect_files(root: &Path) -> Result<Vec<String>, String> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), String> {
+        let entries =
+            fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+            let path = entry.path();
+            let file_type = entry
+                .file_type()
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if file_type.is_symlink() {
+                continue;
+            } else if file_type.is_dir() {
+                walk(root, &path, files)?;
+            } else if let Ok(relative_path) = path.strip_prefix(root) {
+                let components: Vec<String> = relative_path
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect();
+                files.push(components.join("/"));
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(root, root, &mut files)?;
+    files.sort();
+    Ok(files)
+}
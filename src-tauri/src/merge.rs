@@ -0,0 +1,313 @@
+use crate::types::MergeConflict;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSyntax {
+    // tes3mp-server-default.cfg style `[Section]` / `key = value`
+    Ini,
+    // server/scripts/config.lua style `config.key = value` statements
+    Lua,
+}
+
+// A single setting and the lines it spans in its file
+struct Entry {
+    key: String,
+    section: Option<String>,
+    start: usize,
+    end: usize,
+    value: String,
+}
+
+/// Three-way merges a user's edited config (`ours`) onto the defaults of a new
+/// release (`theirs`), using the defaults of the release the user started from
+/// (`base`) to tell user edits apart from upstream changes.
+///
+/// The new defaults are the starting point, so comments and layout follow the
+/// new release. For each setting:
+/// - only the user changed it: the user's value is kept
+/// - only the new release changed it: the new default is used
+/// - both changed it differently: the user's value is kept and a conflict is reported
+/// - the user added it: it is carried over
+/// - the new release dropped it after the user changed it: it is dropped and a conflict is reported
+///
+/// Without a `base` (installs from before defaults were recorded) every
+/// difference is treated as a user edit.
+pub fn three_way_merge(
+    file: &str,
+    base: Option<&str>,
+    ours: &str,
+    theirs: &str,
+    syntax: ConfigSyntax,
+) -> (String, Vec<MergeConflict>) {
+    let base = base.unwrap_or(theirs);
+    let base_lines: Vec<&str> = base.lines().collect();
+    let our_lines: Vec<&str> = ours.lines().collect();
+    let their_lines: Vec<&str> = theirs.lines().collect();
+
+    let base_entries = index_entries(parse_entries(&base_lines, syntax));
+    let our_entries = parse_entries(&our_lines, syntax);
+    let their_entries = parse_entries(&their_lines, syntax);
+
+    let our_index: HashMap<&str, &Entry> =
+        our_entries.iter().map(|e| (e.key.as_str(), e)).collect();
+    let their_index: HashMap<&str, &Entry> =
+        their_entries.iter().map(|e| (e.key.as_str(), e)).collect();
+
+    let mut conflicts = Vec::new();
+    // Start line in theirs -> (entry in ours that replaces it, end line in theirs)
+    let mut replacements: HashMap<usize, (&Entry, usize)> = HashMap::new();
+
+    for their_entry in &their_entries {
+        let Some(our_entry) = our_index.get(their_entry.key.as_str()) else {
+            continue;
+        };
+        let base_value = base_entries.get(&their_entry.key).map(|e| e.value.as_str());
+
+        if our_entry.value == their_entry.value || Some(our_entry.value.as_str()) == base_value {
+            continue;
+        }
+
+        if Some(their_entry.value.as_str()) != base_value && base_value.is_some() {
+            conflicts.push(MergeConflict {
+                file: file.to_string(),
+                key: their_entry.key.clone(),
+                base: base_value.map(|v| v.to_string()),
+                yours: Some(our_entry.value.clone()),
+                theirs: Some(their_entry.value.clone()),
+                resolution: "Kept your value; the new release also changed this setting"
+                    .to_string(),
+            });
+        }
+        replacements.insert(their_entry.start, (our_entry, their_entry.end));
+    }
+
+    // Settings the user has that the new defaults don't
+    let mut additions: Vec<&Entry> = Vec::new();
+    for our_entry in &our_entries {
+        if their_index.contains_key(our_entry.key.as_str()) {
+            continue;
+        }
+        match base_entries.get(&our_entry.key) {
+            None => additions.push(our_entry),
+            Some(base_entry) if base_entry.value != our_entry.value => {
+                conflicts.push(MergeConflict {
+                    file: file.to_string(),
+                    key: our_entry.key.clone(),
+                    base: Some(base_entry.value.clone()),
+                    yours: Some(our_entry.value.clone()),
+                    theirs: None,
+                    resolution: "Dropped; the new release removed this setting".to_string(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    let mut merged: Vec<String> = Vec::new();
+    let mut line = 0;
+    while line < their_lines.len() {
+        if let Some((our_entry, their_end)) = replacements.get(&line) {
+            merged.extend(
+                our_lines[our_entry.start..our_entry.end]
+                    .iter()
+                    .map(|l| l.to_string()),
+            );
+            line = *their_end;
+        } else {
+            merged.push(their_lines[line].to_string());
+            line += 1;
+        }
+    }
+
+    insert_additions(&mut merged, &additions, &our_lines, syntax);
+
+    let mut merged = merged.join("\n");
+    if theirs.ends_with('\n') {
+        merged.push('\n');
+    }
+    (merged, conflicts)
+}
+
+fn index_entries(entries: Vec<Entry>) -> HashMap<String, Entry> {
+    entries.into_iter().map(|e| (e.key.clone(), e)).collect()
+}
+
+fn insert_additions(
+    merged: &mut Vec<String>,
+    additions: &[&Entry],
+    our_lines: &[&str],
+    syntax: ConfigSyntax,
+) {
+    for entry in additions {
+        let text: Vec<String> = our_lines[entry.start..entry.end]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+
+        match syntax {
+            ConfigSyntax::Ini => {
+                let section = entry.section.clone().unwrap_or_default();
+                match section_end(merged, &section) {
+                    Some(at) => {
+                        merged.splice(at..at, text);
+                    }
+                    None => {
+                        merged.push(String::new());
+                        merged.push(format!("[{}]", section));
+                        merged.extend(text);
+                    }
+                }
+            }
+            ConfigSyntax::Lua => {
+                // config.lua ends with `return config`, which has to stay last
+                let at = merged
+                    .iter()
+                    .rposition(|l| l.trim() == "return config")
+                    .unwrap_or(merged.len());
+                merged.splice(at..at, text);
+            }
+        }
+    }
+}
+
+// Index just past the last setting of `section`, or None if the section doesn't exist
+fn section_end(lines: &[String], section: &str) -> Option<usize> {
+    let header = format!("[{}]", section);
+    let start = lines.iter().position(|l| l.trim() == header)?;
+    let mut end = start + 1;
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            break;
+        }
+        if !trimmed.is_empty() {
+            end = i + 1;
+        }
+    }
+    Some(end)
+}
+
+fn parse_entries(lines: &[&str], syntax: ConfigSyntax) -> Vec<Entry> {
+    match syntax {
+        ConfigSyntax::Ini => parse_ini_entries(lines),
+        ConfigSyntax::Lua => parse_lua_entries(lines),
+    }
+}
+
+fn parse_ini_entries(lines: &[&str]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut current_section: Option<String> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+
+        // Check if this is a section header
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            current_section = Some(trimmed[1..trimmed.len() - 1].to_string());
+            continue;
+        }
+
+        // Skip empty lines and comments
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+
+        if let Some((key, value)) = trimmed.split_once('=') {
+            let key = match &current_section {
+                Some(section) => format!("{}.{}", section, key.trim()),
+                None => key.trim().to_string(),
+            };
+            entries.push(Entry {
+                key,
+                section: current_section.clone(),
+                start: i,
+                end: i + 1,
+                value: normalize(value),
+            });
+        }
+    }
+
+    entries
+}
+
+fn parse_lua_entries(lines: &[&str]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut in_block_comment = false;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let trimmed = lines[i].trim();
+
+        if in_block_comment {
+            in_block_comment = !trimmed.contains("]]");
+            i += 1;
+            continue;
+        }
+        if trimmed.starts_with("--[[") {
+            in_block_comment = !trimmed.contains("]]");
+            i += 1;
+            continue;
+        }
+
+        if trimmed.starts_with("config.") {
+            if let Some((key, _)) = trimmed.split_once('=') {
+                // Tables and function calls can span several lines
+                let mut depth = bracket_depth(lines[i]);
+                let mut end = i + 1;
+                while depth > 0 && end < lines.len() {
+                    depth += bracket_depth(lines[end]);
+                    end += 1;
+                }
+
+                let statement = lines[i..end].join("\n");
+                let value = statement.split_once('=').map(|(_, v)| v).unwrap_or("");
+                entries.push(Entry {
+                    key: key.trim().to_string(),
+                    section: None,
+                    start: i,
+                    end,
+                    value: normalize(value),
+                });
+                i = end;
+                continue;
+            }
+        }
+
+        i += 1;
+    }
+
+    entries
+}
+
+// Net change in `{`/`(` nesting on a Lua line, ignoring strings and comments
+fn bracket_depth(line: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                if c == '\\' {
+                    chars.next();
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '-' if chars.peek() == Some(&'-') => break,
+                '{' | '(' => depth += 1,
+                '}' | ')' => depth -= 1,
+                _ => {}
+            },
+        }
+    }
+
+    depth
+}
+
+// Collapse whitespace so formatting differences don't count as edits
+fn normalize(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
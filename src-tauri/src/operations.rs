@@ -0,0 +1,149 @@
+use crate::types::{InstallError, OperationEvent};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+pub const OPERATION_STARTED_EVENT: &str = "operation-started";
+pub const OPERATION_CANCELLED_EVENT: &str = "operation-cancelled";
+
+// Error returned by work that stopped because its operation was cancelled
+pub const CANCELLED: &str = "Operation cancelled";
+
+/// Long-running commands that can be cancelled, by operation ID.
+///
+/// Kept in Tauri state. Callers pick the ID (so they can cancel before the
+/// command returns) or let one be generated and announced with an
+/// `operation-started` event.
+#[derive(Default)]
+pub struct OperationRegistry {
+    operations: Mutex<HashMap<String, (String, CancellationToken)>>,
+    next_id: AtomicU64,
+}
+
+impl OperationRegistry {
+    /// Registers an operation of `kind` (e.g. `install`) under `id`, or under a
+    /// generated ID if none is given. It stays registered until the returned
+    /// [`Operation`] is dropped.
+    pub fn start(
+        &self,
+        app_handle: &tauri::AppHandle,
+        kind: &str,
+        id: Option<String>,
+    ) -> Result<Operation, String> {
+        let id = id.unwrap_or_else(|| {
+            format!(
+                "{}-{}",
+                kind,
+                self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+            )
+        });
+        let token = CancellationToken::new();
+
+        {
+            let mut operations = self
+                .operations
+                .lock()
+                .map_err(|_| "Operation registry is poisoned".to_string())?;
+            if operations.contains_key(&id) {
+                return Err(format!("Operation {} is already running", id));
+            }
+            operations.insert(id.clone(), (kind.to_string(), token.clone()));
+        }
+
+        log::info!("Started operation {} ({})", id, kind);
+        let operation = Operation {
+            app_handle: app_handle.clone(),
+            event: OperationEvent {
+                id,
+                kind: kind.to_string(),
+            },
+            token,
+        };
+        operation.emit(OPERATION_STARTED_EVENT);
+        Ok(operation)
+    }
+
+    /// Signals the operation to stop. Returns false if no operation has that ID.
+    pub fn cancel(&self, id: &str) -> bool {
+        let operations = match self.operations.lock() {
+            Ok(operations) => operations,
+            Err(_) => return false,
+        };
+
+        match operations.get(id) {
+            Some((kind, token)) => {
+                log::warn!("Cancelling operation {} ({})", id, kind);
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn remove(&self, id: &str) {
+        if let Ok(mut operations) = self.operations.lock() {
+            operations.remove(id);
+        }
+    }
+}
+
+/// A registered operation. Dropping it unregisters it, and if it was
+/// cancelled, emits `operation-cancelled` once its cleanup has run.
+pub struct Operation {
+    app_handle: tauri::AppHandle,
+    event: OperationEvent,
+    token: CancellationToken,
+}
+
+impl Operation {
+    pub fn id(&self) -> &str {
+        &self.event.id
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Reports whatever error a cancelled install ended with as [`InstallError::Cancelled`].
+    pub fn finish<T>(&self, result: Result<T, InstallError>) -> Result<T, InstallError> {
+        match result {
+            Err(_) if self.is_cancelled() => Err(InstallError::Cancelled),
+            result => result,
+        }
+    }
+
+    fn emit(&self, event: &str) {
+        if let Err(e) = self.app_handle.emit(event, &self.event) {
+            log::error!("Failed to emit {} event: {}", event, e);
+        }
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        self.app_handle
+            .state::<OperationRegistry>()
+            .remove(&self.event.id);
+
+        if self.token.is_cancelled() {
+            log::info!("Operation {} cancelled", self.event.id);
+            self.emit(OPERATION_CANCELLED_EVENT);
+        }
+    }
+}
+
+/// Fails with [`CANCELLED`] once `token` has been cancelled. Called between
+/// steps of synchronous work, which can't be interrupted mid-way.
+pub fn check_cancelled(token: &CancellationToken) -> Result<(), String> {
+    if token.is_cancelled() {
+        Err(CANCELLED.to_string())
+    } else {
+        Ok(())
+    }
+}
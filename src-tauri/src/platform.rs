@@ -0,0 +1,78 @@
+use crate::types::Platform;
+use std::fs;
+use std::path::Path;
+
+// Programs shipped in a TES3MP release that need to be executable
+const RELEASE_BINARIES: [&str; 5] = [
+    "tes3mp",
+    "tes3mp-server",
+    "tes3mp-browser",
+    "openmw-launcher",
+    "openmw-wizard",
+];
+
+impl Platform {
+    /// The platform this build of Nerevar is running on.
+    pub fn current() -> Platform {
+        if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else {
+            Platform::Linux
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Windows => "windows",
+            Platform::Linux => "linux",
+        }
+    }
+
+    /// File name of the `program` executable on this platform, e.g. `tes3mp.exe` on Windows.
+    pub fn exe_name(&self, program: &str) -> String {
+        match self {
+            Platform::Windows => format!("{}.exe", program),
+            Platform::Linux => program.to_string(),
+        }
+    }
+}
+
+/// Marks the TES3MP and OpenMW binaries in `tes3mp_folder` as executable.
+///
+/// Tarballs usually keep their mode bits, but zips built on Windows don't, so
+/// this is applied after every Linux install. On Windows it does nothing.
+#[cfg(unix)]
+pub fn set_executable_permissions(tes3mp_folder: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let entries =
+        fs::read_dir(tes3mp_folder).map_err(|e| format!("Failed to read TES3MP folder: {}", e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+
+        // Linux releases ship the real binaries as `<name>.x86_64` next to wrapper scripts
+        let program = file_name.strip_suffix(".x86_64").unwrap_or(&file_name);
+        if !path.is_file() || !RELEASE_BINARIES.contains(&program) {
+            continue;
+        }
+
+        let mut permissions = fs::metadata(&path)
+            .map_err(|e| format!("Failed to read permissions of {}: {}", path.display(), e))?
+            .permissions();
+        permissions.set_mode(permissions.mode() | 0o755);
+        fs::set_permissions(&path, permissions)
+            .map_err(|e| format!("Failed to set permissions on {}: {}", path.display(), e))?;
+
+        log::info!("Marked as executable: {}", path.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn set_executable_permissions(_tes3mp_folder: &Path) -> Result<(), String> {
+    Ok(())
+}
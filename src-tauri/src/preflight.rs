@@ -0,0 +1,187 @@
+use crate::archive::ArchiveKind;
+use crate::download::partial_path;
+use crate::types::InstallError;
+use reqwest::header::CONTENT_LENGTH;
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+// Left free on top of what an install needs, so the system isn't left with a full disk
+const HEADROOM: u64 = 64 * 1024 * 1024;
+
+/// Checks that `dir` can be written to by creating and removing a probe file.
+pub fn check_writable(dir: &Path) -> Result<(), InstallError> {
+    let not_writable = |reason: String| InstallError::NotWritable {
+        path: dir.to_string_lossy().to_string(),
+        reason,
+    };
+
+    fs::create_dir_all(dir).map_err(|e| not_writable(e.to_string()))?;
+
+    let probe = dir.join(format!(".nerevar-write-test-{}", std::process::id()));
+    fs::write(&probe, b"").map_err(|e| not_writable(e.to_string()))?;
+    fs::remove_file(&probe).map_err(|e| not_writable(e.to_string()))?;
+    Ok(())
+}
+
+/// Checks that every directory has at least the given number of bytes free.
+/// Directories on the same volume have their requirements added up.
+pub fn check_free_space(requirements: &[(&Path, u64)]) -> Result<(), InstallError> {
+    let mut volumes: Vec<(Option<VolumeId>, &Path, u64)> = Vec::new();
+
+    for (dir, bytes) in requirements {
+        let volume = volume_id(dir);
+        match volumes
+            .iter_mut()
+            .find(|(v, _, _)| volume.is_some() && *v == volume)
+        {
+            Some((_, _, total)) => *total += bytes,
+            None => volumes.push((volume, dir, *bytes)),
+        }
+    }
+
+    for (_, dir, bytes) in volumes {
+        let required = bytes + HEADROOM;
+        let available = fs2::available_space(dir).map_err(|e| InstallError::Failed {
+            message: format!("Failed to read free space of {}: {}", dir.display(), e),
+        })?;
+
+        log::info!(
+            "Free space on {}: {} bytes, need {}",
+            dir.display(),
+            available,
+            required
+        );
+        if available < required {
+            return Err(InstallError::InsufficientSpace {
+                path: dir.to_string_lossy().to_string(),
+                required,
+                available,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Size of `url` from a HEAD request's `Content-Length`, if the server sends one.
+pub async fn content_length(client: &reqwest::Client, url: &str) -> Option<u64> {
+    let response = client.head(url).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response
+        .headers()
+        .get(CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Checks before a download that the temp and destination directories are
+/// writable and that the temp volume can hold what is left of the archive.
+pub async fn check_before_download(
+    client: &reqwest::Client,
+    url: &str,
+    archive_path: &Path,
+    destination_dir: &Path,
+) -> Result<(), InstallError> {
+    let temp_dir = temp_dir_of(archive_path);
+    check_writable(&temp_dir)?;
+    check_writable(destination_dir)?;
+
+    let Some(total) = content_length(client, url).await else {
+        log::warn!("Server did not report the archive size, skipping the space check");
+        return Ok(());
+    };
+    let already_downloaded = fs::metadata(partial_path(archive_path))
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    check_free_space(&[(&temp_dir, total.saturating_sub(already_downloaded))])
+}
+
+/// Checks before extracting `archive_path` that there is room for its contents
+/// in the temp directory and again in `destination_dir`, where it is moved to.
+pub fn check_before_extract(
+    archive_path: &Path,
+    kind: ArchiveKind,
+    destination_dir: &Path,
+) -> Result<(), InstallError> {
+    let temp_dir = std::env::temp_dir();
+    check_writable(&temp_dir)?;
+    check_writable(destination_dir)?;
+
+    let size = uncompressed_size(archive_path, kind)?;
+    log::info!("{} unpacks to about {} bytes", archive_path.display(), size);
+
+    if volume_id(&temp_dir).is_some() && volume_id(&temp_dir) == volume_id(destination_dir) {
+        // The extracted folder is renamed into place, so it only takes up room once
+        check_free_space(&[(&temp_dir, size)])
+    } else {
+        check_free_space(&[(&temp_dir, size), (destination_dir, size)])
+    }
+}
+
+/// Total size of the files in an archive, read from the zip central directory
+/// or the gzip trailer (which only holds the size modulo 4 GiB).
+pub fn uncompressed_size(archive_path: &Path, kind: ArchiveKind) -> Result<u64, String> {
+    let file =
+        File::open(archive_path).map_err(|e| format!("Failed to open archive file: {}", e))?;
+
+    match kind {
+        ArchiveKind::Zip => {
+            let mut archive = zip::ZipArchive::new(BufReader::new(file))
+                .map_err(|e| format!("Failed to read zip archive: {}", e))?;
+            let mut total = 0;
+            for i in 0..archive.len() {
+                let entry = archive
+                    .by_index_raw(i)
+                    .map_err(|e| format!("Failed to read zip entry {}: {}", i, e))?;
+                total += entry.size();
+            }
+            Ok(total)
+        }
+        ArchiveKind::TarGz => {
+            let mut file = file;
+            let mut trailer = [0u8; 4];
+            file.seek(SeekFrom::End(-4))
+                .and_then(|_| file.read_exact(&mut trailer))
+                .map_err(|e| format!("Failed to read gzip trailer: {}", e))?;
+            Ok(u32::from_le_bytes(trailer) as u64)
+        }
+    }
+}
+
+fn temp_dir_of(archive_path: &Path) -> PathBuf {
+    archive_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+#[cfg(unix)]
+type VolumeId = u64;
+#[cfg(not(unix))]
+type VolumeId = String;
+
+// Identifies the volume `dir` (or its nearest existing ancestor) lives on
+#[cfg(unix)]
+fn volume_id(dir: &Path) -> Option<VolumeId> {
+    use std::os::unix::fs::MetadataExt;
+    dir.ancestors()
+        .find_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.dev())
+}
+
+// The drive or UNC share prefix of the path
+#[cfg(not(unix))]
+fn volume_id(dir: &Path) -> Option<VolumeId> {
+    match dir.components().next()? {
+        std::path::Component::Prefix(prefix) => {
+            Some(prefix.as_os_str().to_string_lossy().to_uppercase())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,108 @@
+use crate::supervisor::ProcessSupervisor;
+use crate::types::ProcessStats;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{Emitter, Manager};
+
+pub const PROCESS_STATS_EVENT: &str = "process-stats";
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+// Samples kept per process: ten minutes at SAMPLE_INTERVAL
+const HISTORY_SAMPLES: usize = 120;
+
+/// Recent CPU, memory and thread samples of every supervised process.
+///
+/// Kept in Tauri state. The history of a process is dropped once it exits.
+#[derive(Default)]
+pub struct ProcessStatsHistory {
+    samples: Mutex<HashMap<u32, VecDeque<ProcessStats>>>,
+}
+
+impl ProcessStatsHistory {
+    /// Samples of the process with `pid`, or of every process when None, oldest first.
+    pub fn get(&self, pid: Option<u32>) -> Vec<ProcessStats> {
+        let Ok(samples) = self.samples.lock() else {
+            return Vec::new();
+        };
+        let mut stats: Vec<ProcessStats> = match pid {
+            Some(pid) => samples
+                .get(&pid)
+                .map(|history| history.iter().cloned().collect())
+                .unwrap_or_default(),
+            None => samples.values().flatten().cloned().collect(),
+        };
+        stats.sort_by(|a, b| a.sampled_at.cmp(&b.sampled_at));
+        stats
+    }
+
+    // Adds this round's samples and forgets processes that weren't sampled
+    fn record(&self, latest: &[ProcessStats]) {
+        let Ok(mut samples) = self.samples.lock() else {
+            return;
+        };
+        samples.retain(|pid, _| latest.iter().any(|s| s.pid == *pid));
+        for sample in latest {
+            let history = samples.entry(sample.pid).or_default();
+            if history.len() == HISTORY_SAMPLES {
+                history.pop_front();
+            }
+            history.push_back(sample.clone());
+        }
+    }
+}
+
+/// Starts sampling the supervised processes every few seconds, emitting each
+/// round as a `process-stats` event. Called once from the app's setup.
+pub fn start(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // Kept between rounds, CPU usage is measured since the previous refresh
+        let mut system = System::new();
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let processes = app_handle.state::<ProcessSupervisor>().list();
+            let history = app_handle.state::<ProcessStatsHistory>();
+            if processes.is_empty() {
+                history.record(&[]);
+                continue;
+            }
+
+            let pids: Vec<Pid> = processes.iter().map(|p| Pid::from_u32(p.pid)).collect();
+            system.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&pids),
+                true,
+                ProcessRefreshKind::nothing()
+                    .with_cpu()
+                    .with_memory()
+                    .with_tasks(),
+            );
+
+            let sampled_at = chrono::Local::now().to_rfc3339();
+            let latest: Vec<ProcessStats> = processes
+                .iter()
+                .filter_map(|info| {
+                    let process = system.process(Pid::from_u32(info.pid))?;
+                    Some(ProcessStats {
+                        pid: info.pid,
+                        role: info.role,
+                        cpu_percent: process.cpu_usage(),
+                        memory_bytes: process.memory(),
+                        // Only known on Linux
+                        threads: process.tasks().map(|tasks| tasks.len()),
+                        uptime_seconds: process.run_time(),
+                        sampled_at: sampled_at.clone(),
+                    })
+                })
+                .collect();
+
+            history.record(&latest);
+            if let Err(e) = app_handle.emit(PROCESS_STATS_EVENT, &latest) {
+                log::error!("Failed to emit {} event: {}", PROCESS_STATS_EVENT, e);
+            }
+        }
+    });
+}
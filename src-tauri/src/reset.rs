@@ -0,0 +1,240 @@
+use crate::archive::ArchiveKind;
+use crate::config::get_nerevar_config;
+use crate::installer::{download_release, install_archive, record_install_source, ArchiveVersion};
+use crate::types::{InstallError, Platform, ResetReport};
+use crate::upgrade::user_data_paths;
+use crate::utils::get_appdata_dir;
+use crate::versions::list_installed_versions;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+// Kept through a factory reset so backups can still be restored afterwards
+const BACKUPS_DIR: &str = "backups";
+const MAX_BACKUPS_PER_SECOND: u32 = 100;
+
+/// Wipes Nerevar's data directory: `config.json`, every installed TES3MP
+/// version and the recorded defaults and manifests. Only backups are kept.
+///
+/// With `backup`, the user data of every installed version is backed up first.
+pub fn reset_nerevar(backup: bool) -> Result<ResetReport, String> {
+    let appdata_dir = get_appdata_dir()?;
+    let backup_path = if backup {
+        Some(create_full_backup("nerevar")?)
+    } else {
+        None
+    };
+
+    log::warn!("Factory resetting Nerevar: {}", appdata_dir.display());
+
+    let entries = fs::read_dir(&appdata_dir)
+        .map_err(|e| format!("Failed to read Nerevar data directory: {}", e))?;
+
+    let mut removed = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        if entry.file_name() == BACKUPS_DIR {
+            continue;
+        }
+
+        let path = entry.path();
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        result.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+
+        log::info!("Removed: {}", path.display());
+        removed.push(path.to_string_lossy().to_string());
+    }
+
+    Ok(ResetReport {
+        backup_path: backup_path.map(|p| p.to_string_lossy().to_string()),
+        removed,
+    })
+}
+
+/// Puts the active TES3MP install back to how its release shipped by
+/// reinstalling the same version without carrying anything over. Server data,
+/// custom scripts and config edits are removed; pins and the mode are kept.
+///
+/// The release is downloaded again unless `archive_path` points at a local copy.
+pub async fn reset_tes3mp(
+    app_handle: &tauri::AppHandle,
+    backup: bool,
+    archive_path: Option<String>,
+    cancel: &CancellationToken,
+) -> Result<ResetReport, InstallError> {
+    let config = get_nerevar_config()?
+        .ok_or("No Nerevar config found. Please install TES3MP first.".to_string())?;
+    let install_dir = PathBuf::from(&config.tes3mp_path);
+    let platform = config.platform.unwrap_or(Platform::current());
+
+    // Get the release first, so nothing is lost if it can't be downloaded. A
+    // local archive is only used once it is confirmed to hold the same version.
+    let (archive, kind, source, downloaded) = match archive_path {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            let kind = ArchiveKind::from_name(&path.to_string_lossy())
+                .ok_or("Unsupported archive type (expected .zip or .tar.gz)".to_string())?;
+            let source = path.to_string_lossy().to_string();
+            (path, kind, source, false)
+        }
+        None => {
+            let release =
+                download_release(app_handle, platform, Some(&config.version), cancel).await?;
+            (release.archive_path, release.kind, release.source, true)
+        }
+    };
+
+    let backup_path = if backup {
+        Some(create_backup("tes3mp")?)
+    } else {
+        None
+    };
+
+    let removed: Vec<String> = user_data_paths()
+        .into_iter()
+        .filter(|path| install_dir.join(path).exists())
+        .map(|path| path.to_string())
+        .collect();
+
+    log::warn!(
+        "Factory resetting TES3MP {} in: {}",
+        config.version,
+        install_dir.display()
+    );
+    install_archive(
+        &archive,
+        kind,
+        platform,
+        if downloaded {
+            ArchiveVersion::Known(config.version.clone())
+        } else {
+            ArchiveVersion::Expected(config.version.clone())
+        },
+        false,
+        downloaded,
+        cancel,
+    )?;
+    record_install_source(&config.version, &source);
+
+    Ok(ResetReport {
+        backup_path: backup_path.map(|p| p.to_string_lossy().to_string()),
+        removed,
+    })
+}
+
+/// Zips `config.json` and the server data and configs of the active TES3MP
+/// install into `<appdata>/backups/<label>-<timestamp>.zip`.
+pub fn create_backup(label: &str) -> Result<PathBuf, String> {
+    let installs = match get_nerevar_config()? {
+        Some(config) => vec![("TES3MP".to_string(), PathBuf::from(config.tes3mp_path))],
+        None => Vec::new(),
+    };
+    write_backup(label, &installs)
+}
+
+/// Like [`create_backup`], but with the data of every installed TES3MP version,
+/// each under `TES3MP/<version>`. Taken before the installs themselves go.
+pub fn create_full_backup(label: &str) -> Result<PathBuf, String> {
+    let installs: Vec<(String, PathBuf)> = list_installed_versions()?
+        .into_iter()
+        .map(|installed| {
+            (
+                format!("TES3MP/{}", installed.version),
+                PathBuf::from(installed.tes3mp_path),
+            )
+        })
+        .collect();
+    write_backup(label, &installs)
+}
+
+// Zips `config.json` and the user data of each install, under its folder name
+fn write_backup(label: &str, installs: &[(String, PathBuf)]) -> Result<PathBuf, String> {
+    let appdata_dir = get_appdata_dir()?;
+    let backups_dir = appdata_dir.join(BACKUPS_DIR);
+    fs::create_dir_all(&backups_dir)
+        .map_err(|e| format!("Failed to create backups directory: {}", e))?;
+
+    let (backup_path, file) = create_backup_file(&backups_dir, label)?;
+    log::info!("Creating backup: {}", backup_path.display());
+    let mut zip = ZipWriter::new(file);
+
+    let config_path = appdata_dir.join("config.json");
+    if config_path.exists() {
+        add_to_zip(&mut zip, &config_path, "config.json")?;
+    }
+
+    for (folder, install_dir) in installs {
+        for relative_path in user_data_paths() {
+            let path = install_dir.join(relative_path);
+            if path.exists() {
+                add_to_zip(&mut zip, &path, &format!("{}/{}", folder, relative_path))?;
+            }
+        }
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish backup: {}", e))?;
+
+    log::info!("Backup created: {}", backup_path.display());
+    Ok(backup_path)
+}
+
+// Creates `<label>-<timestamp>.zip`, with a `-2`, `-3`, ... suffix when a
+// backup was already taken in the same second, so none is ever overwritten
+fn create_backup_file(backups_dir: &Path, label: &str) -> Result<(PathBuf, File), String> {
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    for attempt in 1..=MAX_BACKUPS_PER_SECOND {
+        let file_name = match attempt {
+            1 => format!("{}-{}.zip", label, timestamp),
+            n => format!("{}-{}-{}.zip", label, timestamp, n),
+        };
+        let path = backups_dir.join(file_name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to create backup: {}", e)),
+        }
+    }
+    Err(format!(
+        "Failed to create backup: too many {} backups at {}",
+        label, timestamp
+    ))
+}
+
+// Adds a file, or a directory recursively, to the zip under `name`
+fn add_to_zip(zip: &mut ZipWriter<File>, path: &Path, name: &str) -> Result<(), String> {
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    if path.is_dir() {
+        zip.add_directory(name, options)
+            .map_err(|e| format!("Failed to add {} to backup: {}", name, e))?;
+
+        let entries =
+            fs::read_dir(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+            let entry_name = format!("{}/{}", name, entry.file_name().to_string_lossy());
+            add_to_zip(zip, &entry.path(), &entry_name)?;
+        }
+        return Ok(());
+    }
+
+    // Streamed, world data can be too large to read into memory
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    zip.start_file(name, options.large_file(size >= u32::MAX as u64))
+        .map_err(|e| format!("Failed to add {} to backup: {}", name, e))?;
+    io::copy(&mut file, zip).map_err(|e| format!("Failed to write {} to backup: {}", name, e))?;
+    Ok(())
+}
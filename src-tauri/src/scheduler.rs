@@ -0,0 +1,263 @@
+use crate::api::ApiClient;
+use crate::config::get_nerevar_config;
+use crate::cron::CronSchedule;
+use crate::reset::create_backup;
+use crate::server::{start_server, ServerWatchdog};
+use crate::supervisor::ProcessSupervisor;
+use crate::types::{
+    ProcessRole, ScheduleStatus, ScheduledAction, ScheduledActionNotice, ScheduledRun,
+    ScheduledTask,
+};
+use crate::utils::get_appdata_dir;
+use chrono::{DateTime, Local, Timelike};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+pub const SCHEDULED_ACTION_PENDING_EVENT: &str = "scheduled-action-pending";
+pub const SCHEDULED_ACTION_FINISHED_EVENT: &str = "scheduled-action-finished";
+
+// Outcome of each task's last run, kept across app restarts
+const STATE_FILE: &str = "scheduler.json";
+
+/// Runs the scheduled tasks in the Nerevar config. Checked at the start of
+/// every minute, so edits to the schedule apply without a restart.
+///
+/// Kept in Tauri state. Runs that fall while Nerevar is closed are skipped,
+/// not caught up on: the server isn't running then anyway.
+pub struct Scheduler {
+    last_runs: Mutex<HashMap<String, ScheduledRun>>,
+    // Minute each task was last started in, so a minute never runs it twice
+    started: Mutex<HashMap<String, DateTime<Local>>>,
+}
+
+impl Scheduler {
+    /// Loads the results of earlier runs from the app data directory.
+    pub fn load() -> Self {
+        let last_runs = read_state().unwrap_or_else(|e| {
+            log::warn!("Ignoring saved scheduler state: {}", e);
+            HashMap::new()
+        });
+        Self {
+            last_runs: Mutex::new(last_runs),
+            started: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Every task in the config with its next and last run.
+    pub fn status(&self) -> Result<Vec<ScheduleStatus>, String> {
+        let tasks = get_nerevar_config()?
+            .map(|config| config.schedules)
+            .unwrap_or_default();
+        let last_runs = self
+            .last_runs
+            .lock()
+            .map_err(|_| "Scheduler state is poisoned".to_string())?;
+
+        let now = Local::now();
+        Ok(tasks
+            .into_iter()
+            .map(|task| ScheduleStatus {
+                next_run: CronSchedule::parse(&task.cron)
+                    .ok()
+                    .filter(|_| task.enabled)
+                    .and_then(|schedule| schedule.next_after(&now))
+                    .map(|time| time.to_rfc3339()),
+                last_run: last_runs.get(&task.id).cloned(),
+                task,
+            })
+            .collect())
+    }
+
+    // Marks `task_id` as started in `minute`; false if it already was
+    fn try_start(&self, task_id: &str, minute: DateTime<Local>) -> bool {
+        let Ok(mut started) = self.started.lock() else {
+            return false;
+        };
+        if started.get(task_id) == Some(&minute) {
+            return false;
+        }
+        started.insert(task_id.to_string(), minute);
+        true
+    }
+
+    fn record(&self, run: &ScheduledRun) {
+        let Ok(mut last_runs) = self.last_runs.lock() else {
+            return;
+        };
+        last_runs.insert(run.task_id.clone(), run.clone());
+        if let Err(e) = write_state(&last_runs) {
+            log::warn!("Failed to save scheduler state: {}", e);
+        }
+    }
+}
+
+fn state_path() -> Result<PathBuf, String> {
+    Ok(get_appdata_dir()?.join(STATE_FILE))
+}
+
+fn read_state() -> Result<HashMap<String, ScheduledRun>, String> {
+    let path = state_path()?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read scheduler state: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse scheduler state: {}", e))
+}
+
+fn write_state(last_runs: &HashMap<String, ScheduledRun>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(last_runs)
+        .map_err(|e| format!("Failed to serialize scheduler state: {}", e))?;
+    fs::write(state_path()?, json).map_err(|e| format!("Failed to write scheduler state: {}", e))
+}
+
+/// Starts the scheduler loop. Called once from the app's setup.
+pub fn start(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // Wake at the start of the next minute
+            let now = Local::now();
+            let into_minute = Duration::from_secs(now.second() as u64)
+                + Duration::from_nanos(now.nanosecond() as u64);
+            tokio::time::sleep(Duration::from_secs(60).saturating_sub(into_minute)).await;
+
+            let now = Local::now();
+            let minute = now
+                .with_second(0)
+                .and_then(|t| t.with_nanosecond(0))
+                .unwrap_or(now);
+            tick(&app_handle, minute);
+        }
+    });
+}
+
+// Starts the tasks due in `minute` and announces the ones coming up
+fn tick(app_handle: &tauri::AppHandle, minute: DateTime<Local>) {
+    let tasks = match get_nerevar_config() {
+        Ok(Some(config)) => config.schedules,
+        Ok(None) => return,
+        Err(e) => {
+            log::warn!("Scheduler couldn't read the Nerevar config: {}", e);
+            return;
+        }
+    };
+
+    for task in tasks.into_iter().filter(|task| task.enabled) {
+        let schedule = match CronSchedule::parse(&task.cron) {
+            Ok(schedule) => schedule,
+            Err(e) => {
+                log::warn!("Skipping scheduled task {}: {}", task.id, e);
+                continue;
+            }
+        };
+
+        for &minutes_left in &task.warn_minutes {
+            let run_at = minute + chrono::Duration::minutes(minutes_left as i64);
+            if minutes_left > 0 && schedule.matches(&run_at) {
+                announce(app_handle, &task, run_at, minutes_left);
+            }
+        }
+
+        if schedule.matches(&minute) && app_handle.state::<Scheduler>().try_start(&task.id, minute)
+        {
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                run_task(&app_handle, &task).await;
+            });
+        }
+    }
+}
+
+fn announce(
+    app_handle: &tauri::AppHandle,
+    task: &ScheduledTask,
+    run_at: DateTime<Local>,
+    minutes_left: u32,
+) {
+    log::info!(
+        "Scheduled task {} ({:?}) runs in {} minutes",
+        task.id,
+        task.action,
+        minutes_left
+    );
+    let notice = ScheduledActionNotice {
+        task_id: task.id.clone(),
+        action: task.action,
+        run_at: run_at.to_rfc3339(),
+        minutes_left,
+    };
+    if let Err(e) = app_handle.emit(SCHEDULED_ACTION_PENDING_EVENT, &notice) {
+        log::error!(
+            "Failed to emit {} event: {}",
+            SCHEDULED_ACTION_PENDING_EVENT,
+            e
+        );
+    }
+}
+
+/// Runs `task` now, records the outcome and emits `scheduled-action-finished`.
+pub async fn run_task(app_handle: &tauri::AppHandle, task: &ScheduledTask) -> ScheduledRun {
+    log::info!("Running scheduled task {} ({:?})", task.id, task.action);
+    let ran_at = Local::now().to_rfc3339();
+
+    let result = match task.action {
+        ScheduledAction::RestartServer => restart_server(app_handle).await,
+        ScheduledAction::BackupWorld => backup_world().await,
+        ScheduledAction::CheckForUpdates => check_for_updates(app_handle).await,
+    };
+    match &result {
+        Ok(message) => log::info!("Scheduled task {} finished: {}", task.id, message),
+        Err(e) => log::error!("Scheduled task {} failed: {}", task.id, e),
+    }
+
+    let run = ScheduledRun {
+        task_id: task.id.clone(),
+        action: task.action,
+        ran_at,
+        success: result.is_ok(),
+        message: result.unwrap_or_else(|e| e),
+    };
+    app_handle.state::<Scheduler>().record(&run);
+    if let Err(e) = app_handle.emit(SCHEDULED_ACTION_FINISHED_EVENT, &run) {
+        log::error!(
+            "Failed to emit {} event: {}",
+            SCHEDULED_ACTION_FINISHED_EVENT,
+            e
+        );
+    }
+    run
+}
+
+async fn restart_server(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    let supervisor = app_handle.state::<ProcessSupervisor>();
+    let Some(server) = supervisor.running(ProcessRole::Server) else {
+        return Ok("TES3MP server is not running, nothing to restart".to_string());
+    };
+
+    supervisor.stop(server.pid).await?;
+    // A deliberate restart starts the crash count over
+    app_handle.state::<ServerWatchdog>().reset();
+    let pid = start_server(app_handle, false)?;
+    Ok(format!("TES3MP server restarted (PID: {})", pid))
+}
+
+async fn backup_world() -> Result<String, String> {
+    let backup_path = tokio::task::spawn_blocking(|| create_backup("scheduled"))
+        .await
+        .map_err(|e| format!("Backup task failed: {}", e))??;
+    Ok(format!("Backup created: {}", backup_path.display()))
+}
+
+async fn check_for_updates(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    let update = crate::commands::check_for_tes3mp_update(app_handle.state::<ApiClient>()).await?;
+    Ok(if update.update_available {
+        format!("TES3MP {} is available", update.version)
+    } else {
+        format!("TES3MP {} is up to date", update.current_version)
+    })
+}
@@ -0,0 +1,250 @@
+use crate::config::get_nerevar_config;
+use crate::server_log::ServerLog;
+use crate::supervisor::ProcessSupervisor;
+use crate::types::{
+    CrashReport, Platform, ProcessExit, ProcessRole, ServerRestart, WatchdogPolicy, WatchdogStatus,
+};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+pub const SERVER_RESTARTING_EVENT: &str = "tes3mp-server-restarting";
+pub const SERVER_CRASH_LOOP_EVENT: &str = "tes3mp-server-crash-loop";
+
+// Lines of server output kept with each crash
+const CRASH_LOG_LINES: usize = 30;
+
+// Starts a console program without a console window
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// Starts the TES3MP server of the active install under the supervisor, with
+/// its input and output connected to the server console. Returns its PID.
+pub fn start_server(app_handle: &tauri::AppHandle, allow_duplicate: bool) -> Result<u32, String> {
+    // Get the nerevar config to find the TES3MP installation path
+    let nerevar_config =
+        get_nerevar_config().map_err(|e| format!("Failed to get Nerevar config: {}", e))?;
+
+    let config = nerevar_config.ok_or("No Nerevar config found. Please install TES3MP first.")?;
+    let tes3mp_path = config.tes3mp_path;
+
+    // Construct the path to the TES3MP server executable
+    let server_path = Path::new(&tes3mp_path).join(Platform::current().exe_name("tes3mp-server"));
+
+    // Check if the TES3MP server executable exists
+    if !server_path.exists() {
+        return Err(format!(
+            "TES3MP server not found at: {}",
+            server_path.display()
+        ));
+    }
+
+    log::info!("Running TES3MP server at: {}", server_path.display());
+
+    // Connect the server to the server console instead of a terminal window
+    let mut command = tokio::process::Command::new(&server_path);
+    command
+        .current_dir(&tes3mp_path)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    // Otherwise Windows opens an empty console window for it
+    #[cfg(windows)]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    app_handle.state::<ProcessSupervisor>().spawn(
+        app_handle,
+        ProcessRole::Server,
+        command,
+        allow_duplicate,
+    )
+}
+
+/// Restarts the server when it crashes, if the watchdog policy in the Nerevar
+/// config is enabled.
+///
+/// Kept in Tauri state. Restarts back off exponentially; once the server has
+/// crashed more than `max_restarts` times within the window it is left down
+/// (a crash loop) until it is started by hand or the watchdog is reset.
+#[derive(Default)]
+pub struct ServerWatchdog {
+    state: Mutex<WatchdogState>,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+    // Crashes within the policy window, with when they happened
+    crashes: VecDeque<(Instant, CrashReport)>,
+    crash_loop: bool,
+    // Cancelled to call off the scheduled restart
+    pending: Option<CancellationToken>,
+}
+
+impl ServerWatchdog {
+    pub fn status(&self) -> WatchdogStatus {
+        let policy = current_policy();
+        match self.state.lock() {
+            Ok(state) => WatchdogStatus {
+                policy,
+                crash_loop: state.crash_loop,
+                restart_pending: state.pending.is_some(),
+                recent_crashes: state.crashes.iter().map(|(_, c)| c.clone()).collect(),
+            },
+            Err(_) => WatchdogStatus {
+                policy,
+                crash_loop: false,
+                restart_pending: false,
+                recent_crashes: Vec::new(),
+            },
+        }
+    }
+
+    /// Forgets past crashes, ends a crash loop and calls off a scheduled restart.
+    pub fn reset(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(pending) = state.pending.take() {
+                log::info!("Calling off scheduled TES3MP server restart");
+                pending.cancel();
+            }
+            state.crashes.clear();
+            state.crash_loop = false;
+        }
+    }
+
+    // Records `crash` and returns the attempt number, delay and cancellation
+    // token of the restart to schedule, or None once it is a crash loop
+    fn record_crash(
+        &self,
+        policy: &WatchdogPolicy,
+        crash: CrashReport,
+    ) -> Option<(u32, Duration, CancellationToken)> {
+        let mut state = self.state.lock().ok()?;
+        let now = Instant::now();
+        let window = Duration::from_secs(policy.window_seconds);
+
+        state
+            .crashes
+            .retain(|(at, _)| now.duration_since(*at) <= window);
+        state.crashes.push_back((now, crash));
+        if let Some(pending) = state.pending.take() {
+            pending.cancel();
+        }
+
+        let attempt = state.crashes.len() as u32;
+        if attempt > policy.max_restarts {
+            state.crash_loop = true;
+            return None;
+        }
+
+        let token = CancellationToken::new();
+        state.pending = Some(token.clone());
+        Some((attempt, backoff(policy, attempt), token))
+    }
+
+    // Clears the scheduled restart if it is still the one for `token`
+    fn take_pending(&self, token: &CancellationToken) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        if token.is_cancelled() {
+            return false;
+        }
+        state.pending = None;
+        true
+    }
+}
+
+fn current_policy() -> WatchdogPolicy {
+    match get_nerevar_config() {
+        Ok(Some(config)) => config.watchdog,
+        _ => WatchdogPolicy::default(),
+    }
+}
+
+// initial, 2 * initial, 4 * initial, ... capped at the maximum
+fn backoff(policy: &WatchdogPolicy, attempt: u32) -> Duration {
+    let factor = 1u64
+        .checked_shl(attempt.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+    Duration::from_secs(
+        policy
+            .initial_backoff_seconds
+            .saturating_mul(factor)
+            .min(policy.max_backoff_seconds),
+    )
+}
+
+/// Called by the supervisor when the server exits. Schedules a restart if the
+/// server crashed and the watchdog is enabled, or reports a crash loop.
+pub fn server_exited(app_handle: &tauri::AppHandle, exit: &ProcessExit) {
+    if exit.requested || exit.success {
+        return;
+    }
+    let policy = current_policy();
+    if !policy.enabled {
+        return;
+    }
+
+    let crash = CrashReport {
+        pid: exit.pid,
+        exit_code: exit.exit_code,
+        crashed_at: chrono::Local::now().to_rfc3339(),
+        log_tail: app_handle
+            .state::<ServerLog>()
+            .tail(exit.pid, CRASH_LOG_LINES),
+    };
+    log::warn!(
+        "TES3MP server (PID: {}) crashed with exit code {:?}",
+        crash.pid,
+        crash.exit_code
+    );
+
+    let watchdog = app_handle.state::<ServerWatchdog>();
+    match watchdog.record_crash(&policy, crash.clone()) {
+        Some((attempt, delay, token)) => {
+            log::info!(
+                "Restarting TES3MP server in {:?} (attempt {} of {})",
+                delay,
+                attempt,
+                policy.max_restarts
+            );
+            let restart = ServerRestart {
+                attempt,
+                delay_seconds: delay.as_secs(),
+                crash,
+            };
+            if let Err(e) = app_handle.emit(SERVER_RESTARTING_EVENT, &restart) {
+                log::error!("Failed to emit {} event: {}", SERVER_RESTARTING_EVENT, e);
+            }
+            tokio::spawn(restart_after(app_handle.clone(), delay, token));
+        }
+        None => {
+            log::error!(
+                "TES3MP server crashed more than {} times within {} seconds, not restarting it",
+                policy.max_restarts,
+                policy.window_seconds
+            );
+            if let Err(e) = app_handle.emit(SERVER_CRASH_LOOP_EVENT, &watchdog.status()) {
+                log::error!("Failed to emit {} event: {}", SERVER_CRASH_LOOP_EVENT, e);
+            }
+        }
+    }
+}
+
+async fn restart_after(app_handle: tauri::AppHandle, delay: Duration, token: CancellationToken) {
+    tokio::select! {
+        _ = token.cancelled() => return,
+        _ = tokio::time::sleep(delay) => {}
+    }
+    if !app_handle.state::<ServerWatchdog>().take_pending(&token) {
+        return;
+    }
+
+    match start_server(&app_handle, false) {
+        Ok(pid) => log::info!("TES3MP server restarted (PID: {})", pid),
+        Err(e) => log::error!("Failed to restart TES3MP server: {}", e),
+    }
+}
@@ -0,0 +1,305 @@
+use crate::types::{LogStream, ServerLogLine};
+use crate::utils::get_appdata_dir;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+
+pub const SERVER_LOG_EVENT: &str = "tes3mp-server-log";
+
+// Lines kept in memory for consoles opened after the server started
+const BUFFERED_LINES: usize = 5000;
+
+// Commands sent to the server that are kept for the console's history
+const HISTORY_LENGTH: usize = 100;
+
+// Longer output without a newline is split into lines of this many bytes
+const MAX_LINE_LENGTH: u64 = 16 * 1024;
+
+// Lines waiting to be written to the log file; more are dropped from the file
+// (not the console) until the writer catches up
+const WRITE_QUEUE_LINES: usize = 5000;
+
+// Server logs go to `<appdata>/logs/tes3mp-server.log`, rotated to `.1`, `.2`,
+// ... once they reach MAX_FILE_SIZE, keeping KEPT_FILES old files
+const LOGS_DIR: &str = "logs";
+const LOG_FILE_NAME: &str = "tes3mp-server.log";
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+const KEPT_FILES: usize = 5;
+
+/// Console of the TES3MP server: the latest lines of output in memory, every
+/// line in rotating log files, and the commands sent to it.
+///
+/// Kept in Tauri state. Each line is also emitted as a `tes3mp-server-log` event.
+#[derive(Default)]
+pub struct ServerLog {
+    state: Mutex<LogState>,
+}
+
+#[derive(Default)]
+struct LogState {
+    lines: VecDeque<ServerLogLine>,
+    // Feeds the thread writing the log file, started with the first line
+    writer: Option<SyncSender<String>>,
+    // Lines left out of the log file since the queue was last full
+    dropped: u64,
+    history: VecDeque<String>,
+}
+
+struct LogFile {
+    file: File,
+    size: u64,
+}
+
+impl ServerLog {
+    /// The buffered lines, oldest first, or only the last `limit` of them.
+    pub fn lines(&self, limit: Option<usize>) -> Vec<ServerLogLine> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+        let skip = limit.map_or(0, |limit| state.lines.len().saturating_sub(limit));
+        state.lines.iter().skip(skip).cloned().collect()
+    }
+
+    /// The last `count` lines the process with `pid` printed, oldest first.
+    pub fn tail(&self, pid: u32, count: usize) -> Vec<String> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+        let mut tail: Vec<String> = state
+            .lines
+            .iter()
+            .rev()
+            .filter(|line| line.pid == pid && line.stream != LogStream::Stdin)
+            .take(count)
+            .map(|line| line.line.clone())
+            .collect();
+        tail.reverse();
+        tail
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.lines.clear();
+        }
+    }
+
+    /// Commands sent to the server, oldest first.
+    pub fn history(&self) -> Vec<String> {
+        match self.state.lock() {
+            Ok(state) => state.history.iter().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Adds a command sent to the server to the history, and echoes it to the
+    /// console like the server's own output.
+    pub fn record_command(&self, app_handle: &tauri::AppHandle, pid: u32, command: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            // Repeating the last command doesn't add another entry
+            if state.history.back().map(String::as_str) != Some(command) {
+                if state.history.len() == HISTORY_LENGTH {
+                    state.history.pop_front();
+                }
+                state.history.push_back(command.to_string());
+            }
+        }
+
+        self.push(
+            app_handle,
+            ServerLogLine {
+                pid,
+                stream: LogStream::Stdin,
+                line: command.to_string(),
+                timestamp: chrono::Local::now().to_rfc3339(),
+            },
+        );
+    }
+
+    fn push(&self, app_handle: &tauri::AppHandle, line: ServerLogLine) {
+        if let Ok(mut state) = self.state.lock() {
+            state.queue_write(&line);
+            if state.lines.len() == BUFFERED_LINES {
+                state.lines.pop_front();
+            }
+            state.lines.push_back(line.clone());
+        }
+
+        if let Err(e) = app_handle.emit(SERVER_LOG_EVENT, &line) {
+            log::error!("Failed to emit {} event: {}", SERVER_LOG_EVENT, e);
+        }
+    }
+}
+
+impl LogState {
+    // Hands the line to the writer thread, so file IO never blocks the caller
+    // or holds the lock
+    fn queue_write(&mut self, line: &ServerLogLine) {
+        if self.writer.is_none() {
+            self.writer = spawn_writer();
+        }
+        let Some(writer) = &self.writer else {
+            return;
+        };
+
+        if self.dropped > 0 {
+            let note = format!(
+                "{} [nerevar] {} lines were left out of this log, it couldn't be written fast enough\n",
+                line.timestamp, self.dropped
+            );
+            if writer.try_send(note).is_err() {
+                self.dropped += 1;
+                return;
+            }
+            self.dropped = 0;
+        }
+
+        let text = format!(
+            "{} [{}] {}\n",
+            line.timestamp,
+            line.stream.as_str(),
+            line.line
+        );
+        match writer.try_send(text) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => self.dropped += 1,
+            Err(TrySendError::Disconnected(_)) => {
+                log::warn!("Server log writer stopped, restarting it");
+                self.writer = None;
+            }
+        }
+    }
+}
+
+fn spawn_writer() -> Option<SyncSender<String>> {
+    let (sender, receiver) = mpsc::sync_channel::<String>(WRITE_QUEUE_LINES);
+    let spawned = std::thread::Builder::new()
+        .name("server-log".to_string())
+        .spawn(move || {
+            let mut file = None;
+            for text in receiver {
+                write_to_file(&mut file, &text);
+            }
+        });
+    match spawned {
+        Ok(_) => Some(sender),
+        Err(e) => {
+            log::warn!("Failed to start server log writer: {}", e);
+            None
+        }
+    }
+}
+
+fn write_to_file(file: &mut Option<LogFile>, text: &str) {
+    if file
+        .as_ref()
+        .is_some_and(|f| f.size + text.len() as u64 > MAX_FILE_SIZE)
+    {
+        *file = None;
+        if let Err(e) = rotate_log_files() {
+            log::warn!("Failed to rotate server logs: {}", e);
+        }
+    }
+    if file.is_none() {
+        match open_log_file() {
+            Ok(opened) => *file = Some(opened),
+            Err(e) => {
+                log::warn!("{}", e);
+                return;
+            }
+        }
+    }
+
+    if let Some(log_file) = file.as_mut() {
+        match log_file.file.write_all(text.as_bytes()) {
+            Ok(()) => log_file.size += text.len() as u64,
+            Err(e) => {
+                // Reopened (and the failure logged again) on the next line
+                log::warn!("Failed to write server log: {}", e);
+                *file = None;
+            }
+        }
+    }
+}
+
+pub fn logs_dir() -> Result<PathBuf, String> {
+    Ok(get_appdata_dir()?.join(LOGS_DIR))
+}
+
+fn open_log_file() -> Result<LogFile, String> {
+    let dir = logs_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create logs directory: {}", e))?;
+
+    let path = dir.join(LOG_FILE_NAME);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open server log {}: {}", path.display(), e))?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    Ok(LogFile { file, size })
+}
+
+// tes3mp-server.log.4 -> .5, ..., tes3mp-server.log -> .1, dropping the oldest
+fn rotate_log_files() -> Result<(), String> {
+    let dir = logs_dir()?;
+    let numbered = |n: usize| dir.join(format!("{}.{}", LOG_FILE_NAME, n));
+
+    let oldest = numbered(KEPT_FILES);
+    if oldest.exists() {
+        fs::remove_file(&oldest).map_err(|e| format!("Failed to remove old server log: {}", e))?;
+    }
+    for n in (1..KEPT_FILES).rev() {
+        let from = numbered(n);
+        if from.exists() {
+            fs::rename(&from, numbered(n + 1))
+                .map_err(|e| format!("Failed to rotate server log: {}", e))?;
+        }
+    }
+    fs::rename(dir.join(LOG_FILE_NAME), numbered(1))
+        .map_err(|e| format!("Failed to rotate server log: {}", e))
+}
+
+/// Reads the server's piped stdout or stderr line by line into the
+/// [`ServerLog`] until the stream closes.
+pub async fn capture<R>(app_handle: tauri::AppHandle, pid: u32, stream: LogStream, output: R)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut reader = BufReader::new(output);
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        // A line without a newline in sight is cut up, not buffered forever
+        let read = (&mut reader)
+            .take(MAX_LINE_LENGTH)
+            .read_until(b'\n', &mut buffer)
+            .await;
+        match read {
+            Ok(0) => break,
+            Ok(_) => {
+                // The server doesn't promise UTF-8 (mods print whatever they like)
+                let line = String::from_utf8_lossy(&buffer)
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string();
+                app_handle.state::<ServerLog>().push(
+                    &app_handle,
+                    ServerLogLine {
+                        pid,
+                        stream,
+                        line,
+                        timestamp: chrono::Local::now().to_rfc3339(),
+                    },
+                );
+            }
+            Err(e) => {
+                log::warn!("Failed to read server {}: {}", stream.as_str(), e);
+                break;
+            }
+        }
+    }
+}
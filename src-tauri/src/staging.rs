@@ -0,0 +1,152 @@
+use crate::utils::copy_dir_recursive;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Staging and backup folders sit next to the install they belong to, so the
+// final renames never cross a filesystem. The leading dot keeps them out of the
+// installed versions list.
+fn sibling_path(target: &Path, prefix: &str) -> Result<PathBuf, String> {
+    let parent = target
+        .parent()
+        .ok_or(format!("{} has no parent directory", target.display()))?;
+    let name = target
+        .file_name()
+        .ok_or(format!("{} has no folder name", target.display()))?;
+    Ok(parent.join(format!(".{}-{}", prefix, name.to_string_lossy())))
+}
+
+/// Moves a directory, falling back to copy + delete when `fs::rename` can't
+/// (e.g. temp and AppData on different volumes).
+pub fn move_dir(source: &Path, target: &Path) -> Result<(), String> {
+    match fs::rename(source, target) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            log::warn!(
+                "Rename from {} to {} failed ({}), copying instead",
+                source.display(),
+                target.display(),
+                e
+            );
+
+            if let Err(copy_err) = copy_dir_recursive(source, target) {
+                // Don't leave a half-copied directory behind
+                let _ = fs::remove_dir_all(target);
+                return Err(copy_err);
+            }
+            fs::remove_dir_all(source)
+                .map_err(|e| format!("Failed to remove {}: {}", source.display(), e))
+        }
+    }
+}
+
+/// Moves `source` into a staging folder next to `target` and returns its path.
+pub fn stage_dir(source: &Path, target: &Path) -> Result<PathBuf, String> {
+    let staging_path = sibling_path(target, "staging")?;
+
+    if staging_path.exists() {
+        log::info!(
+            "Removing leftover staging directory: {}",
+            staging_path.display()
+        );
+        fs::remove_dir_all(&staging_path)
+            .map_err(|e| format!("Failed to remove leftover staging directory: {}", e))?;
+    }
+
+    if let Some(parent) = staging_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    log::info!(
+        "Staging {} in: {}",
+        source.display(),
+        staging_path.display()
+    );
+    move_dir(source, &staging_path)?;
+    Ok(staging_path)
+}
+
+/// Puts back an install left half-swapped by a crash: restores the backup when
+/// the target is missing, or drops it when the swap had already finished.
+pub fn recover_interrupted_swap(target: &Path) -> Result<(), String> {
+    let backup_path = sibling_path(target, "backup")?;
+    if !backup_path.exists() {
+        return Ok(());
+    }
+
+    if target.exists() {
+        log::info!("Removing stale backup: {}", backup_path.display());
+        fs::remove_dir_all(&backup_path)
+            .map_err(|e| format!("Failed to remove stale backup: {}", e))
+    } else {
+        log::warn!("Restoring {} from an interrupted install", target.display());
+        fs::rename(&backup_path, target)
+            .map_err(|e| format!("Failed to restore interrupted install: {}", e))
+    }
+}
+
+/// A staged directory swapped into place, with the directory it replaced kept
+/// aside until the swap is committed or rolled back.
+pub struct DirSwap {
+    target: PathBuf,
+    backup: Option<PathBuf>,
+}
+
+impl DirSwap {
+    /// Replaces `target` with `staging`. Both are in the same directory, so each
+    /// step is a single rename; if the second one fails the original is put back.
+    pub fn swap_in(staging: &Path, target: &Path) -> Result<DirSwap, String> {
+        recover_interrupted_swap(target)?;
+
+        let backup = if target.exists() {
+            let backup_path = sibling_path(target, "backup")?;
+            fs::rename(target, &backup_path)
+                .map_err(|e| format!("Failed to move existing install aside: {}", e))?;
+            log::info!("Existing install moved aside to: {}", backup_path.display());
+            Some(backup_path)
+        } else {
+            None
+        };
+
+        if let Err(e) = fs::rename(staging, target) {
+            if let Some(backup_path) = &backup {
+                if let Err(restore_err) = fs::rename(backup_path, target) {
+                    log::error!("Failed to restore existing install: {}", restore_err);
+                }
+            }
+            return Err(format!("Failed to swap in new install: {}", e));
+        }
+
+        log::info!("Swapped in: {}", target.display());
+        Ok(DirSwap {
+            target: target.to_path_buf(),
+            backup,
+        })
+    }
+
+    /// Keeps the new directory and deletes the one it replaced.
+    pub fn commit(self) {
+        if let Some(backup_path) = &self.backup {
+            // The swap itself succeeded, a leftover backup is cleaned up next time
+            if let Err(e) = fs::remove_dir_all(backup_path) {
+                log::warn!("Failed to remove backup {}: {}", backup_path.display(), e);
+            }
+        }
+    }
+
+    /// Removes the new directory and puts the replaced one back.
+    pub fn rollback(self) -> Result<(), String> {
+        log::warn!("Rolling back install of: {}", self.target.display());
+
+        if self.target.exists() {
+            fs::remove_dir_all(&self.target)
+                .map_err(|e| format!("Failed to remove failed install: {}", e))?;
+        }
+        if let Some(backup_path) = &self.backup {
+            fs::rename(backup_path, &self.target)
+                .map_err(|e| format!("Failed to restore previous install: {}", e))?;
+            log::info!("Restored previous install: {}", self.target.display());
+        }
+        Ok(())
+    }
+}
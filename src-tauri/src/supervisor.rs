@@ -0,0 +1,385 @@
+use crate::server_log::capture;
+use crate::types::{LogStream, ProcessExit, ProcessInfo, ProcessRole};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+// How long `stop` gives a process to exit before killing it
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+// How long to keep reading output left in the pipes after a process exits
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+impl ProcessRole {
+    /// Name used in logs and messages, e.g. `TES3MP server`.
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ProcessRole::Wizard => "OpenMW wizard",
+            ProcessRole::Launcher => "OpenMW launcher",
+            ProcessRole::Browser => "TES3MP browser",
+            ProcessRole::Client => "TES3MP",
+            ProcessRole::Server => "TES3MP server",
+        }
+    }
+
+    /// Prefix of the `<prefix>-started` and `<prefix>-exited` events.
+    pub fn event_prefix(&self) -> &'static str {
+        match self {
+            ProcessRole::Wizard => "openmw-wizard",
+            ProcessRole::Launcher => "openmw-launcher",
+            ProcessRole::Browser => "tes3mp-browser",
+            ProcessRole::Client => "tes3mp",
+            ProcessRole::Server => "tes3mp-server",
+        }
+    }
+
+    // Two clients or servers fight over the same config, saves and ports, so a
+    // second one is only started when asked for explicitly
+    fn is_exclusive(&self) -> bool {
+        matches!(self, ProcessRole::Client | ProcessRole::Server)
+    }
+}
+
+// Taken (and so closed) when the process is asked to stop
+type SharedStdin = Arc<tokio::sync::Mutex<Option<ChildStdin>>>;
+
+struct Supervised {
+    info: ProcessInfo,
+    controls: Controls,
+}
+
+#[derive(Clone)]
+struct Controls {
+    // Cancelled to make the monitor task kill the process
+    kill: CancellationToken,
+    // Cancelled by the monitor task once the process has exited
+    exited: CancellationToken,
+    // Set for processes spawned with piped stdin
+    stdin: Option<SharedStdin>,
+    // Set by `stop` and `kill`, so the exit isn't taken for a crash
+    requested: Arc<AtomicBool>,
+}
+
+/// Every program Nerevar has launched that is still running, by PID.
+///
+/// Kept in Tauri state. Each process is waited on by its own task, which
+/// emits `<prefix>-exited` and drops it from the supervisor when it exits.
+#[derive(Default)]
+pub struct ProcessSupervisor {
+    processes: Mutex<HashMap<u32, Supervised>>,
+}
+
+impl ProcessSupervisor {
+    /// Spawns `command` as a process of `role`, emits `<prefix>-started` with
+    /// its PID and returns the PID. Refuses to start a second client or server
+    /// unless `allow_duplicate` is set.
+    pub fn spawn(
+        &self,
+        app_handle: &tauri::AppHandle,
+        role: ProcessRole,
+        mut command: Command,
+        allow_duplicate: bool,
+    ) -> Result<u32, String> {
+        let name = role.display_name();
+        let mut processes = self
+            .processes
+            .lock()
+            .map_err(|_| "Process supervisor is poisoned".to_string())?;
+
+        if role.is_exclusive() && !allow_duplicate {
+            if let Some(running) = processes.values().find(|p| p.info.role == role) {
+                return Err(format!(
+                    "{} is already running (PID: {})",
+                    name, running.info.pid
+                ));
+            }
+        }
+
+        let executable = command.as_std().get_program().to_string_lossy().to_string();
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", name, e))?;
+        let pid = child
+            .id()
+            .ok_or(format!("{} exited as soon as it started", name))?;
+        log::info!("{} started successfully (PID: {})", name, pid);
+
+        let controls = Controls {
+            kill: CancellationToken::new(),
+            exited: CancellationToken::new(),
+            stdin: child
+                .stdin
+                .take()
+                .map(|stdin| Arc::new(tokio::sync::Mutex::new(Some(stdin)))),
+            requested: Arc::new(AtomicBool::new(false)),
+        };
+        processes.insert(
+            pid,
+            Supervised {
+                info: ProcessInfo {
+                    pid,
+                    role,
+                    executable,
+                    started_at: chrono::Utc::now().to_rfc3339(),
+                },
+                controls: controls.clone(),
+            },
+        );
+        drop(processes);
+
+        let started_event = format!("{}-started", role.event_prefix());
+        if let Err(e) = app_handle.emit(&started_event, &pid) {
+            log::error!("Failed to emit {} event: {}", started_event, e);
+        }
+
+        // Only the server has its output piped, it feeds the server console
+        let mut output = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            output.push(tokio::spawn(capture(
+                app_handle.clone(),
+                pid,
+                LogStream::Stdout,
+                stdout,
+            )));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            output.push(tokio::spawn(capture(
+                app_handle.clone(),
+                pid,
+                LogStream::Stderr,
+                stderr,
+            )));
+        }
+
+        tokio::spawn(monitor(
+            app_handle.clone(),
+            child,
+            pid,
+            role,
+            controls,
+            output,
+        ));
+        Ok(pid)
+    }
+
+    pub fn list(&self) -> Vec<ProcessInfo> {
+        let mut processes: Vec<ProcessInfo> = match self.processes.lock() {
+            Ok(processes) => processes.values().map(|p| p.info.clone()).collect(),
+            Err(_) => Vec::new(),
+        };
+        processes.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        processes
+    }
+
+    /// The oldest running process of `role`, if any.
+    pub fn running(&self, role: ProcessRole) -> Option<ProcessInfo> {
+        self.list().into_iter().find(|p| p.role == role)
+    }
+
+    /// Writes `line` and a newline to the process's stdin.
+    pub async fn write_stdin(&self, pid: u32, line: &str) -> Result<(), String> {
+        let (role, controls) = self.controls(pid)?;
+        let stdin = controls.stdin.ok_or(format!(
+            "{} does not accept console input",
+            role.display_name()
+        ))?;
+
+        let mut stdin = stdin.lock().await;
+        let writer = stdin
+            .as_mut()
+            .ok_or(format!("{} is shutting down", role.display_name()))?;
+        let write = async {
+            writer.write_all(format!("{}\n", line).as_bytes()).await?;
+            writer.flush().await
+        };
+        write
+            .await
+            .map_err(|e| format!("Failed to write to {}: {}", role.display_name(), e))
+    }
+
+    /// Asks the process to shut down by closing its stdin and sending it a
+    /// termination signal, and kills it if it is still running after a few
+    /// seconds. Returns once it has exited.
+    pub async fn stop(&self, pid: u32) -> Result<(), String> {
+        let (role, controls) = self.controls(pid)?;
+        log::info!("Stopping {} (PID: {})", role.display_name(), pid);
+        controls.requested.store(true, Ordering::SeqCst);
+
+        // Console programs reading commands exit once their input ends
+        let closed_stdin = match &controls.stdin {
+            Some(stdin) => stdin.lock().await.take().is_some(),
+            None => false,
+        };
+
+        // It may have exited, and its PID been reused, while stdin was locked
+        if controls.exited.is_cancelled() {
+            return Ok(());
+        }
+        if let Err(e) = terminate(pid) {
+            if closed_stdin {
+                log::warn!("{}, waiting for it to exit on its own", e);
+            } else {
+                log::warn!("{}, killing it instead", e);
+                controls.kill.cancel();
+            }
+        }
+
+        tokio::select! {
+            _ = controls.exited.cancelled() => {}
+            _ = tokio::time::sleep(STOP_TIMEOUT) => {
+                log::warn!(
+                    "{} (PID: {}) did not stop within {:?}",
+                    role.display_name(),
+                    pid,
+                    STOP_TIMEOUT
+                );
+                controls.kill.cancel();
+                controls.exited.cancelled().await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Kills the process outright. Returns once it has exited.
+    pub async fn kill(&self, pid: u32) -> Result<(), String> {
+        let (_, controls) = self.controls(pid)?;
+        controls.requested.store(true, Ordering::SeqCst);
+        controls.kill.cancel();
+        controls.exited.cancelled().await;
+        Ok(())
+    }
+
+    fn controls(&self, pid: u32) -> Result<(ProcessRole, Controls), String> {
+        let processes = self
+            .processes
+            .lock()
+            .map_err(|_| "Process supervisor is poisoned".to_string())?;
+        let process = processes.get(&pid).ok_or(format!(
+            "No process with PID {} was started by Nerevar",
+            pid
+        ))?;
+        Ok((process.info.role, process.controls.clone()))
+    }
+
+    fn remove(&self, pid: u32) {
+        if let Ok(mut processes) = self.processes.lock() {
+            processes.remove(&pid);
+        }
+    }
+}
+
+// Waits for the process to exit (killing it if asked to) and for its output to
+// be read, then unregisters it and emits `<prefix>-exited`
+async fn monitor(
+    app_handle: tauri::AppHandle,
+    mut child: Child,
+    pid: u32,
+    role: ProcessRole,
+    controls: Controls,
+    output: Vec<JoinHandle<()>>,
+) {
+    let name = role.display_name();
+    let status = tokio::select! {
+        status = child.wait() => status,
+        _ = controls.kill.cancelled() => {
+            log::warn!("Killing {} (PID: {})", name, pid);
+            if let Err(e) = child.start_kill() {
+                log::error!("Failed to kill {} (PID: {}): {}", name, pid, e);
+            }
+            child.wait().await
+        }
+    };
+
+    // Unregister as soon as it is reaped: its PID can be reused from here on,
+    // and `stop` must not signal whatever process gets it next
+    app_handle.state::<ProcessSupervisor>().remove(pid);
+    controls.exited.cancel();
+
+    // A process that left children behind can hold its pipes open, so don't
+    // wait on them for long
+    let deadline = tokio::time::Instant::now() + OUTPUT_DRAIN_TIMEOUT;
+    for reader in output {
+        let _ = tokio::time::timeout_at(deadline, reader).await;
+    }
+    let requested = controls.requested.load(Ordering::SeqCst);
+
+    let event_data = match status {
+        Ok(status) => {
+            log::info!(
+                "{} (PID: {}) exited with status: {:?}",
+                name,
+                pid,
+                status.code()
+            );
+            ProcessExit {
+                pid,
+                success: status.success(),
+                exit_code: status.code(),
+                message: if status.success() {
+                    format!("{} completed successfully", name)
+                } else {
+                    format!("{} exited with an error", name)
+                },
+                requested,
+            }
+        }
+        Err(e) => {
+            log::error!("Failed to wait for {} (PID: {}): {}", name, pid, e);
+            ProcessExit {
+                pid,
+                success: false,
+                exit_code: None,
+                message: format!("Failed to wait for {}: {}", name, e),
+                requested,
+            }
+        }
+    };
+
+    let exited_event = format!("{}-exited", role.event_prefix());
+    if let Err(e) = app_handle.emit(&exited_event, &event_data) {
+        log::error!("Failed to emit {} event: {}", exited_event, e);
+    }
+
+    if role == ProcessRole::Server {
+        crate::server::server_exited(&app_handle, &event_data);
+    }
+}
+
+// Sends SIGTERM, which TES3MP and OpenMW handle by shutting down cleanly
+#[cfg(unix)]
+fn terminate(pid: u32) -> Result<(), String> {
+    let status = std::process::Command::new("kill")
+        .args(["-TERM", &pid.to_string()])
+        .status()
+        .map_err(|e| format!("Failed to signal PID {}: {}", pid, e))?;
+    if !status.success() {
+        return Err(format!(
+            "Failed to signal PID {}: kill exited with {}",
+            pid, status
+        ));
+    }
+    Ok(())
+}
+
+// Without /F, taskkill asks the program to close its windows. Console programs
+// have none, so this fails for them and they are killed instead
+#[cfg(not(unix))]
+fn terminate(pid: u32) -> Result<(), String> {
+    let status = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string()])
+        .status()
+        .map_err(|e| format!("Failed to ask PID {} to close: {}", pid, e))?;
+    if !status.success() {
+        return Err(format!(
+            "Failed to ask PID {} to close: taskkill exited with {}",
+            pid, status
+        ));
+    }
+    Ok(())
+}
@@ -7,19 +7,533 @@ pub enum Mode {
     Server,
 }
 
+// Which builds of Nerevar and TES3MP updates come from, most stable first
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Beta => "beta",
+            Channel::Nightly => "nightly",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Windows,
+    Linux,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct UpdateCheckResponse {
+    // Only true when the latest release is newer than the installed one
     pub update_available: bool,
     pub version: String,
     pub url: Option<String>,
+    pub current_version: String,
+    pub comparison: VersionComparison,
+    // Markdown notes and publish date of the latest release
+    pub release_notes: Option<String>,
+    pub release_date: Option<String>,
+    // Size of the download for this platform in bytes, when known
+    pub download_size: Option<u64>,
+    // True if any release between the installed and the latest one is breaking
+    pub breaking: bool,
+    // Every release after the installed one up to the latest, newest first
+    pub changelog: Vec<ReleaseNotes>,
 }
 
-#[derive(Serialize, Deserialize)]
+// What changed in one release, as listed in a changelog
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReleaseNotes {
+    pub version: String,
+    #[serde(default)]
+    pub release_date: Option<String>,
+    // Markdown
+    #[serde(default)]
+    pub notes: String,
+    // Set when updating needs manual steps, e.g. server data migrations
+    #[serde(default)]
+    pub breaking: bool,
+}
+
+// How the latest published release relates to the installed version
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionComparison {
+    Newer,
+    Older,
+    Equal,
+    // One of the versions couldn't be parsed
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReleaseInfo {
+    pub version: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    // Other URLs serving the same archive as `url`, tried in order if it fails
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    // Hex encoded SHA-256 of the release archive
+    #[serde(default)]
+    pub sha256: Option<String>,
+    // Base64 ed25519 signature over the SHA-256 digest
+    #[serde(default)]
+    pub signature: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+    // Markdown release notes
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub release_date: Option<String>,
+    #[serde(default)]
+    pub breaking: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReleaseAsset {
+    pub platform: Platform,
+    pub url: String,
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub signature: Option<String>,
+    // Size of the archive in bytes
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DownloadProgress {
+    pub bytes_done: u64,
+    pub total: Option<u64>,
+    // Bytes per second over the current transfer
+    pub rate: f64,
+    pub eta_seconds: Option<u64>,
+}
+
+// Payload of the `operation-started` and `operation-cancelled` events
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OperationEvent {
+    pub id: String,
+    pub kind: String,
+}
+
+// Programs Nerevar launches and supervises
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessRole {
+    Wizard,
+    Launcher,
+    Browser,
+    Client,
+    Server,
+}
+
+// A running process, as returned by `list_processes`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub role: ProcessRole,
+    pub executable: String,
+    pub started_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+    // Commands sent to the server from Nerevar
+    Stdin,
+}
+
+impl LogStream {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+            LogStream::Stdin => "stdin",
+        }
+    }
+}
+
+// A line of server console output, the payload of `tes3mp-server-log` events
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServerLogLine {
+    pub pid: u32,
+    pub stream: LogStream,
+    pub line: String,
+    pub timestamp: String,
+}
+
+// One resource sample of a supervised process, see `get_process_stats`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProcessStats {
+    pub pid: u32,
+    pub role: ProcessRole,
+    // Since the previous sample, 100 per fully used core
+    pub cpu_percent: f32,
+    // Resident set size
+    pub memory_bytes: u64,
+    pub threads: Option<usize>,
+    pub uptime_seconds: u64,
+    pub sampled_at: String,
+}
+
+// Payload of the `<program>-exited` events
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProcessExit {
+    pub pid: u32,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub message: String,
+    // Stopped or killed from Nerevar rather than exiting on its own
+    pub requested: bool,
+}
+
+// A server crash seen by the watchdog
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrashReport {
+    pub pid: u32,
+    pub exit_code: Option<i32>,
+    pub crashed_at: String,
+    // The last lines the server printed before it crashed
+    pub log_tail: Vec<String>,
+}
+
+// Payload of the `tes3mp-server-restarting` event
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServerRestart {
+    // Restarts within the current window, including this one
+    pub attempt: u32,
+    pub delay_seconds: u64,
+    pub crash: CrashReport,
+}
+
+// Returned by `get_watchdog_status`, and the payload of `tes3mp-server-crash-loop`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WatchdogStatus {
+    pub policy: WatchdogPolicy,
+    // Set when the server crashed too often and is no longer restarted
+    pub crash_loop: bool,
+    pub restart_pending: bool,
+    // Crashes within the policy's window, oldest first
+    pub recent_crashes: Vec<CrashReport>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MergeConflict {
+    pub file: String,
+    pub key: String,
+    pub base: Option<String>,
+    pub yours: Option<String>,
+    pub theirs: Option<String>,
+    // What the merge did with the setting
+    pub resolution: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpgradeReport {
+    pub version: String,
+    pub tes3mp_path: String,
+    // Paths relative to the install that were carried over or merged
+    pub preserved: Vec<String>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct NerevarConfig {
+    // The active TES3MP install, used unless a server is pinned to another version
     pub tes3mp_path: String,
     pub version: String,
     pub last_updated: String,
     pub mode: Option<Mode>,
+    // Platform the TES3MP install was built for; older configs predate Linux support
+    #[serde(default)]
+    pub platform: Option<Platform>,
+    // Version that was active before the current one, for rollback
+    #[serde(default)]
+    pub previous_version: Option<String>,
+    // Server address (`ip:port`) -> TES3MP version used to connect to it
+    #[serde(default)]
+    pub pinned_versions: std::collections::HashMap<String, String>,
+    // Self-hosted Nerevar API, e.g. for staging or private communities
+    #[serde(default)]
+    pub api_url: Option<String>,
+    // Use the local stand-in API on localhost instead of `api_url`
+    #[serde(default)]
+    pub use_local_api: bool,
+    // Release channel for both app and TES3MP updates
+    #[serde(default)]
+    pub channel: Channel,
+    // Restarting the TES3MP server when it crashes, off unless enabled
+    #[serde(default)]
+    pub watchdog: WatchdogPolicy,
+    // Maintenance run by the scheduler, e.g. nightly server restarts
+    #[serde(default)]
+    pub schedules: Vec<ScheduledTask>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledAction {
+    RestartServer,
+    BackupWorld,
+    CheckForUpdates,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduledTask {
+    pub id: String,
+    // Cron expression in local time, e.g. `0 4 * * *` for 4am every day
+    pub cron: String,
+    pub action: ScheduledAction,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    // Minutes before each run to emit `scheduled-action-pending`, so players can be warned
+    #[serde(default = "default_warn_minutes")]
+    pub warn_minutes: Vec<u32>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_warn_minutes() -> Vec<u32> {
+    vec![10, 1]
+}
+
+// Payload of the `scheduled-action-pending` event
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduledActionNotice {
+    pub task_id: String,
+    pub action: ScheduledAction,
+    pub run_at: String,
+    pub minutes_left: u32,
+}
+
+// Outcome of a scheduled task, the payload of `scheduled-action-finished`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduledRun {
+    pub task_id: String,
+    pub action: ScheduledAction,
+    pub ran_at: String,
+    pub success: bool,
+    pub message: String,
+}
+
+// Returned by `get_schedules`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduleStatus {
+    pub task: ScheduledTask,
+    pub next_run: Option<String>,
+    pub last_run: Option<ScheduledRun>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WatchdogPolicy {
+    pub enabled: bool,
+    // Restarts allowed within `window_seconds` before the server is left down
+    pub max_restarts: u32,
+    pub window_seconds: u64,
+    // Delay before the first restart, doubled for each further crash in the window
+    pub initial_backoff_seconds: u64,
+    pub max_backoff_seconds: u64,
+}
+
+impl Default for WatchdogPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_restarts: 5,
+            window_seconds: 600,
+            initial_backoff_seconds: 2,
+            max_backoff_seconds: 60,
+        }
+    }
+}
+
+// Why an install or update failed. Pre-flight failures carry the details the
+// UI needs; everything else is a plain message.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InstallError {
+    InsufficientSpace {
+        path: String,
+        required: u64,
+        available: u64,
+    },
+    NotWritable {
+        path: String,
+        reason: String,
+    },
+    // The channel's latest release is older than the installed one and the
+    // caller didn't ask to downgrade
+    DowngradeRequired {
+        installed: String,
+        available: String,
+        channel: Channel,
+    },
+    // No checksum is known for the release, e.g. a GitHub fallback release
+    // that GitHub has no digest for and Nerevar doesn't pin
+    ChecksumUnavailable {
+        version: String,
+        url: String,
+    },
+    Cancelled,
+    Failed {
+        message: String,
+    },
+}
+
+impl std::fmt::Display for InstallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallError::InsufficientSpace {
+                path,
+                required,
+                available,
+            } => write!(
+                f,
+                "Not enough disk space on {}: {} MB needed, {} MB free",
+                path,
+                required / (1024 * 1024),
+                available / (1024 * 1024)
+            ),
+            InstallError::NotWritable { path, reason } => {
+                write!(f, "Cannot write to {}: {}", path, reason)
+            }
+            InstallError::DowngradeRequired {
+                installed,
+                available,
+                channel,
+            } => write!(
+                f,
+                "The latest {} release, TES3MP {}, is older than the installed {}; confirm the downgrade to install it",
+                channel.as_str(),
+                available,
+                installed
+            ),
+            InstallError::ChecksumUnavailable { version, url } => write!(
+                f,
+                "No SHA-256 checksum is known for TES3MP {} ({}), so it can't be installed from this source; try again once the Nerevar API is reachable",
+                version, url
+            ),
+            InstallError::Cancelled => write!(f, "Cancelled"),
+            InstallError::Failed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for InstallError {
+    fn from(message: String) -> Self {
+        InstallError::Failed { message }
+    }
+}
+
+impl From<&str> for InstallError {
+    fn from(message: &str) -> Self {
+        InstallError::Failed {
+            message: message.to_string(),
+        }
+    }
+}
+
+// Written next to each install as `versions/<version>.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstallMetadata {
+    pub version: String,
+    pub tes3mp_path: String,
+    #[serde(default)]
+    pub platform: Option<Platform>,
+    #[serde(default)]
+    pub installed_at: Option<String>,
+    // URL (or local archive path) the release was installed from
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+// Size and hash of every release file, written as `versions/<version>.manifest.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstallManifest {
+    pub version: String,
+    #[serde(default)]
+    pub platform: Option<Platform>,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManifestEntry {
+    // Relative to the install, `/`-separated
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestSource {
+    // Recorded when the version was installed
+    Local,
+    // Fetched from the Nerevar API
+    Api,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IntegrityReport {
+    pub version: String,
+    pub tes3mp_path: String,
+    pub manifest_source: ManifestSource,
+    pub checked: usize,
+    pub missing: Vec<String>,
+    pub modified: Vec<String>,
+    // Files not in the release, user data excluded
+    pub extra: Vec<String>,
+    pub healthy: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RepairReport {
+    pub version: String,
+    pub repaired: Vec<String>,
+    // Verification after the repair
+    pub report: IntegrityReport,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResetReport {
+    // Zip of config.json and server data/configs taken before the reset
+    pub backup_path: Option<String>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstalledVersion {
+    pub version: String,
+    pub tes3mp_path: String,
+    pub platform: Option<Platform>,
+    pub installed_at: Option<String>,
+    pub source: Option<String>,
+    pub active: bool,
+    // Servers (`ip:port`) pinned to this version
+    pub pinned_servers: Vec<String>,
 }
 
 // Use a flexible map for OpenMW config since it can contain any settings
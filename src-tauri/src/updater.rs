@@ -0,0 +1,284 @@
+use crate::integrity::verify_app_update;
+use crate::preflight::check_writable;
+use crate::version_compare::parse_version;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Makes the app print its version and exit, used to check a staged update runs
+pub const VERSION_FLAG: &str = "--version";
+
+// Emitted with a `DownloadProgress` payload while an update downloads
+pub const APP_UPDATE_PROGRESS_EVENT: &str = "app-update-download-progress";
+
+// How long a staged update gets to answer `--version`
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A Nerevar release offered by `check_for_app_update`.
+#[derive(Debug, Clone)]
+pub struct AppUpdate {
+    pub version: String,
+    pub url: String,
+    pub sha256: Option<String>,
+    pub signature: Option<String>,
+}
+
+/// The update offered by the last check and its verified download, kept in
+/// Tauri state so only an update the API offered is downloaded and applied.
+#[derive(Default)]
+pub struct AppUpdates {
+    state: Mutex<UpdateState>,
+}
+
+#[derive(Default)]
+struct UpdateState {
+    offered: Option<AppUpdate>,
+    downloaded: Option<(AppUpdate, PathBuf)>,
+}
+
+impl AppUpdates {
+    /// Remembers `update` as the one to download, or clears it when None.
+    pub fn offer(&self, update: Option<AppUpdate>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.offered = update;
+            state.downloaded = None;
+        }
+    }
+
+    pub fn offered(&self) -> Result<AppUpdate, String> {
+        self.state
+            .lock()
+            .map_err(|_| "Update state is poisoned".to_string())?
+            .offered
+            .clone()
+            .ok_or("No update has been offered, check for updates first".to_string())
+    }
+
+    /// Records `path` as the verified download of `update`, unless another
+    /// check has offered a different update in the meantime.
+    pub fn set_downloaded(&self, update: AppUpdate, path: PathBuf) -> Result<(), String> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| "Update state is poisoned".to_string())?;
+        if state.offered.as_ref().map(|o| &o.url) != Some(&update.url) {
+            return Err("The offered update changed while downloading".to_string());
+        }
+        state.downloaded = Some((update, path));
+        Ok(())
+    }
+
+    pub fn take_downloaded(&self) -> Result<(AppUpdate, PathBuf), String> {
+        self.state
+            .lock()
+            .map_err(|_| "Update state is poisoned".to_string())?
+            .downloaded
+            .take()
+            .ok_or("No update has been downloaded".to_string())
+    }
+}
+
+/// Prints the app version and returns true if the app was started with
+/// `--version`, before any window is created.
+pub fn handle_version_flag() -> bool {
+    if std::env::args().nth(1).as_deref() != Some(VERSION_FLAG) {
+        return false;
+    }
+    println!("Nerevar {}", env!("CARGO_PKG_VERSION"));
+    true
+}
+
+/// The file an update replaces: the AppImage when running from one (the
+/// executable itself lives in a read-only mount), otherwise the executable.
+pub fn update_target() -> Result<PathBuf, String> {
+    #[cfg(target_os = "linux")]
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+
+    std::env::current_exe().map_err(|e| format!("Failed to get current executable path: {}", e))
+}
+
+// `<dir>/.<name>.new` and `<dir>/<name>.previous`, next to the target so the
+// swap is a rename within one directory
+fn sibling_paths(target: &Path) -> Result<(PathBuf, PathBuf), String> {
+    let dir = target
+        .parent()
+        .ok_or(format!("{} has no parent directory", target.display()))?;
+    let name = target
+        .file_name()
+        .ok_or(format!("{} has no file name", target.display()))?
+        .to_string_lossy();
+    Ok((
+        dir.join(format!(".{}.new", name)),
+        dir.join(format!("{}.previous", name)),
+    ))
+}
+
+/// Checks the download of `update` against its signature, stages it next to
+/// the running build, checks that it starts and reports the offered version,
+/// then swaps it in. The replaced build is kept as `<name>.previous` for
+/// [`rollback_update`]. Returns the new version.
+pub async fn install_update(downloaded: &Path, update: &AppUpdate) -> Result<String, String> {
+    verify_app_update(
+        downloaded,
+        update.sha256.as_deref(),
+        update.signature.as_deref(),
+    )?;
+
+    let target = update_target()?;
+    let (staged, previous) = sibling_paths(&target)?;
+    log::info!(
+        "Updating {} from: {}",
+        target.display(),
+        downloaded.display()
+    );
+
+    let dir = target.parent().unwrap_or(Path::new("."));
+    check_writable(dir).map_err(|e| {
+        format!(
+            "Nerevar can't update itself in place ({}), install the update manually",
+            e
+        )
+    })?;
+
+    fs::copy(downloaded, &staged).map_err(|e| format!("Failed to stage update: {}", e))?;
+    // Check the staged copy too, the download could have changed since it was verified
+    let staged_ok = verify_app_update(
+        &staged,
+        update.sha256.as_deref(),
+        update.signature.as_deref(),
+    )
+    .and_then(|_| make_executable(&staged));
+    let version = match staged_ok {
+        Ok(()) => verify_build(&staged).await,
+        Err(e) => Err(e),
+    };
+    // A validly signed build of another version, e.g. from a replayed older
+    // update manifest, must not be swapped in
+    let version = version.and_then(|version| {
+        if parse_version(&version).is_some_and(|v| Some(v) == parse_version(&update.version)) {
+            Ok(version)
+        } else {
+            Err(format!(
+                "Update reports version {} but {} was expected, refusing to install it",
+                version, update.version
+            ))
+        }
+    });
+    let version = match version {
+        Ok(version) => version,
+        Err(e) => {
+            let _ = fs::remove_file(&staged);
+            return Err(e);
+        }
+    };
+
+    if let Err(e) = swap_in(&staged, &target, &previous) {
+        let _ = fs::remove_file(&staged);
+        return Err(e);
+    }
+    if let Err(e) = fs::remove_file(downloaded) {
+        log::warn!("Failed to remove downloaded update: {}", e);
+    }
+
+    log::info!("Nerevar {} installed to: {}", version, target.display());
+    Ok(version)
+}
+
+/// Puts the build kept by the last update back in place. The build it
+/// replaces is kept in turn, so a rollback can itself be undone.
+pub fn rollback_update() -> Result<(), String> {
+    let target = update_target()?;
+    let (staged, previous) = sibling_paths(&target)?;
+    if !previous.exists() {
+        return Err("No previous version of Nerevar to roll back to".to_string());
+    }
+
+    log::warn!("Rolling back Nerevar to: {}", previous.display());
+    fs::rename(&previous, &staged)
+        .map_err(|e| format!("Failed to stage previous version: {}", e))?;
+    swap_in(&staged, &target, &previous)
+}
+
+/// Runs `executable --version` and returns the version it prints.
+pub async fn verify_build(executable: &Path) -> Result<String, String> {
+    let mut command = tokio::process::Command::new(executable);
+    command
+        .arg(VERSION_FLAG)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(VERIFY_TIMEOUT, command.output())
+        .await
+        .map_err(|_| "Update did not respond to --version, it may be corrupt".to_string())?
+        .map_err(|e| format!("Failed to run update: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("Update exited with {} when started", output.status));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout
+        .split_whitespace()
+        .last()
+        .filter(|v| parse_version(v).is_some())
+        .ok_or(format!(
+            "Update did not report a valid version: {}",
+            stdout.trim()
+        ))?;
+
+    log::info!("Staged update reports version: {}", version);
+    Ok(version.to_string())
+}
+
+// Swaps `staged` in as `target`, keeping what was there as `previous`
+#[cfg(unix)]
+fn swap_in(staged: &Path, target: &Path, previous: &Path) -> Result<(), String> {
+    // Keep a link to the current build, then replace it with one atomic rename;
+    // the target path always points at a complete build
+    if previous.exists() {
+        fs::remove_file(previous)
+            .map_err(|e| format!("Failed to remove old previous version: {}", e))?;
+    }
+    if fs::hard_link(target, previous).is_err() {
+        fs::copy(target, previous)
+            .map_err(|e| format!("Failed to keep previous version: {}", e))?;
+    }
+
+    fs::rename(staged, target).map_err(|e| format!("Failed to swap in update: {}", e))
+}
+
+// Swaps `staged` in as `target`, keeping what was there as `previous`
+#[cfg(not(unix))]
+fn swap_in(staged: &Path, target: &Path, previous: &Path) -> Result<(), String> {
+    // A running executable can't be overwritten on Windows, but it can be
+    // renamed, so move it aside first and put it back if the second rename fails
+    if previous.exists() {
+        fs::remove_file(previous)
+            .map_err(|e| format!("Failed to remove old previous version: {}", e))?;
+    }
+    fs::rename(target, previous)
+        .map_err(|e| format!("Failed to move current version aside: {}", e))?;
+
+    if let Err(e) = fs::rename(staged, target) {
+        if let Err(restore_err) = fs::rename(previous, target) {
+            log::error!("Failed to restore current version: {}", restore_err);
+        }
+        return Err(format!("Failed to swap in update: {}", e));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .map_err(|e| format!("Failed to make update executable: {}", e))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use crate::manifest::read_manifest;
+use crate::merge::{three_way_merge, ConfigSyntax};
+use crate::types::MergeConflict;
+use crate::utils::{copy_dir_recursive, get_appdata_dir};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// User data that is carried over as-is from the old install
+const PRESERVED_PATHS: [&str; 3] = [
+    "server/data",
+    "server/scripts/custom",
+    "server/scripts/customScripts.lua",
+];
+
+// Config files that are merged against the new release's defaults
+const MERGED_CONFIGS: [(&str, ConfigSyntax); 2] = [
+    ("server/scripts/config.lua", ConfigSyntax::Lua),
+    ("tes3mp-server-default.cfg", ConfigSyntax::Ini),
+];
+
+// Edited in place by Nerevar when connecting to a server
+const CLIENT_CONFIG: &str = "tes3mp-client-default.cfg";
+
+/// Paths in an install that hold user data or user-edited configs.
+pub fn user_data_paths() -> Vec<&'static str> {
+    PRESERVED_PATHS
+        .iter()
+        .copied()
+        .chain(MERGED_CONFIGS.iter().map(|(path, _)| *path))
+        .chain([CLIENT_CONFIG])
+        .collect()
+}
+
+/// Whether `relative_path` (relative to an install, `/`-separated) is user data
+/// or a user-edited config rather than a file shipped with the release.
+pub fn is_user_data(relative_path: &str) -> bool {
+    relative_path == CLIENT_CONFIG
+        || MERGED_CONFIGS
+            .iter()
+            .any(|(path, _)| *path == relative_path)
+        || PRESERVED_PATHS
+            .iter()
+            .any(|path| relative_path == *path || relative_path.starts_with(&format!("{}/", path)))
+}
+
+/// Where the untouched default configs of a TES3MP release are kept. They are
+/// the merge base the next time that install is upgraded.
+pub fn defaults_dir(version: &str) -> Result<PathBuf, String> {
+    Ok(get_appdata_dir()?.join("defaults").join(version))
+}
+
+/// Records the default configs shipped with a freshly extracted release, before
+/// anything is merged into them.
+pub fn snapshot_defaults(tes3mp_folder: &Path, version: &str) -> Result<(), String> {
+    let snapshot_dir = defaults_dir(version)?;
+
+    for (relative_path, _) in MERGED_CONFIGS {
+        let source = tes3mp_folder.join(relative_path);
+        if !source.exists() {
+            continue;
+        }
+
+        let target = snapshot_dir.join(relative_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create defaults directory: {}", e))?;
+        }
+        fs::copy(&source, &target)
+            .map_err(|e| format!("Failed to record default {}: {}", relative_path, e))?;
+    }
+
+    log::info!(
+        "Recorded TES3MP {} defaults in: {}",
+        version,
+        snapshot_dir.display()
+    );
+    Ok(())
+}
+
+/// Copies server data and custom scripts from `old_install` into `new_install`
+/// and merges the user's config edits onto the new defaults.
+///
+/// `previous_version` selects the recorded defaults used as the merge base.
+/// Returns the relative paths that were carried over and any merge conflicts.
+pub fn carry_over_user_data(
+    old_install: &Path,
+    new_install: &Path,
+    previous_version: Option<&str>,
+) -> Result<(Vec<String>, Vec<MergeConflict>), String> {
+    let mut preserved = Vec::new();
+    let mut conflicts = Vec::new();
+
+    if !old_install.exists() {
+        log::info!("No existing TES3MP install to carry data over from");
+        return Ok((preserved, conflicts));
+    }
+
+    log::info!(
+        "Carrying user data over from {} to {}",
+        old_install.display(),
+        new_install.display()
+    );
+
+    for relative_path in PRESERVED_PATHS {
+        let source = old_install.join(relative_path);
+        if !source.exists() {
+            continue;
+        }
+
+        let target = new_install.join(relative_path);
+        if target.is_dir() {
+            fs::remove_dir_all(&target)
+                .map_err(|e| format!("Failed to replace {}: {}", relative_path, e))?;
+        }
+        if source.is_dir() {
+            copy_dir_recursive(&source, &target)?;
+        } else {
+            fs::copy(&source, &target)
+                .map_err(|e| format!("Failed to preserve {}: {}", relative_path, e))?;
+        }
+
+        log::info!("Preserved: {}", relative_path);
+        preserved.push(relative_path.to_string());
+    }
+
+    // Scripts the user dropped straight into server/scripts. Scripts the old
+    // release shipped are left out, the new release may have dropped them on
+    // purpose.
+    let release_files = match previous_version.map(read_manifest).transpose()?.flatten() {
+        Some(manifest) => manifest.files.into_iter().map(|file| file.path).collect(),
+        None => {
+            log::warn!("No manifest of the previous release, carrying over every missing script");
+            HashSet::new()
+        }
+    };
+    copy_missing_files(
+        &old_install.join("server/scripts"),
+        &new_install.join("server/scripts"),
+        "server/scripts",
+        &release_files,
+        &mut preserved,
+    )?;
+
+    let base_dir = match previous_version {
+        Some(version) => Some(defaults_dir(version)?),
+        None => None,
+    };
+
+    for (relative_path, syntax) in MERGED_CONFIGS {
+        let ours_path = old_install.join(relative_path);
+        let theirs_path = new_install.join(relative_path);
+        if !ours_path.exists() {
+            continue;
+        }
+
+        let ours = fs::read_to_string(&ours_path)
+            .map_err(|e| format!("Failed to read existing {}: {}", relative_path, e))?;
+
+        let merged = if theirs_path.exists() {
+            let theirs = fs::read_to_string(&theirs_path)
+                .map_err(|e| format!("Failed to read new {}: {}", relative_path, e))?;
+            let base = base_dir
+                .as_ref()
+                .and_then(|dir| fs::read_to_string(dir.join(relative_path)).ok());
+            if base.is_none() {
+                log::warn!(
+                    "No recorded defaults for {}, treating every difference as a user edit",
+                    relative_path
+                );
+            }
+
+            let (merged, file_conflicts) =
+                three_way_merge(relative_path, base.as_deref(), &ours, &theirs, syntax);
+            for conflict in &file_conflicts {
+                log::warn!(
+                    "Merge conflict in {} for {}: {}",
+                    conflict.file,
+                    conflict.key,
+                    conflict.resolution
+                );
+            }
+            conflicts.extend(file_conflicts);
+            merged
+        } else {
+            ours
+        };
+
+        fs::write(&theirs_path, merged)
+            .map_err(|e| format!("Failed to write merged {}: {}", relative_path, e))?;
+
+        log::info!("Merged: {}", relative_path);
+        preserved.push(relative_path.to_string());
+    }
+
+    Ok((preserved, conflicts))
+}
+
+// Copies files under `source` that don't exist under `target` and aren't in
+// `release_files`, recursively
+fn copy_missing_files(
+    source: &Path,
+    target: &Path,
+    relative_path: &str,
+    release_files: &HashSet<String>,
+    preserved: &mut Vec<String>,
+) -> Result<(), String> {
+    if !source.is_dir() {
+        return Ok(());
+    }
+
+    let entries =
+        fs::read_dir(source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let entry_relative_path = format!("{}/{}", relative_path, name);
+
+        // These are handled by PRESERVED_PATHS/MERGED_CONFIGS
+        if PRESERVED_PATHS.contains(&entry_relative_path.as_str())
+            || MERGED_CONFIGS
+                .iter()
+                .any(|(path, _)| *path == entry_relative_path)
+        {
+            continue;
+        }
+        if release_files.contains(&entry_relative_path) {
+            continue;
+        }
+
+        let source_path = entry.path();
+        let target_path = target.join(&name);
+
+        if source_path.is_dir() {
+            copy_missing_files(
+                &source_path,
+                &target_path,
+                &entry_relative_path,
+                release_files,
+                preserved,
+            )?;
+        } else if !target_path.exists() {
+            fs::create_dir_all(target)
+                .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+            fs::copy(&source_path, &target_path)
+                .map_err(|e| format!("Failed to preserve {}: {}", entry_relative_path, e))?;
+            log::info!("Preserved custom script: {}", entry_relative_path);
+            preserved.push(entry_relative_path);
+        }
+    }
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use crate::types::Platform;
 use dirs;
 use std::fs;
 use std::path::Path;
@@ -11,9 +12,23 @@ pub fn get_documents_folder() -> Result<std::path::PathBuf, String> {
     }
 }
 
-pub fn find_tes3mp_folder(extract_path: &Path) -> Result<std::path::PathBuf, String> {
+pub fn find_tes3mp_folder(
+    extract_path: &Path,
+    platform: Platform,
+) -> Result<std::path::PathBuf, String> {
     log::info!("Scanning directory: {}", extract_path.display());
 
+    // Some archives have the binaries at the top level instead of in a subfolder
+    let client_exe = platform.exe_name("tes3mp");
+    if extract_path.join(&client_exe).is_file() {
+        log::info!(
+            "Found {} directly in extraction folder: {}",
+            client_exe,
+            extract_path.display()
+        );
+        return Ok(extract_path.to_path_buf());
+    }
+
     let entries = fs::read_dir(extract_path)
         .map_err(|e| format!("Failed to read extraction directory: {}", e))?;
 
@@ -34,14 +49,24 @@ pub fn find_tes3mp_folder(extract_path: &Path) -> Result<std::path::PathBuf, Str
             log::info!("Checking folder: '{}'", folder_name);
             found_folders.push(folder_name.to_string());
 
-            // Look for folders that start with "tes3mp" (case insensitive)
-            if folder_name.to_lowercase().starts_with("tes3mp") {
-                log::info!("Found matching TES3MP folder: {}", path.display());
+            // Windows folders look like tes3mp.Win64.release.0.8.1, Linux ones are just TES3MP,
+            // so prefer whichever folder actually holds the client binary
+            if path.join(&client_exe).is_file() {
+                log::info!("Found {} in folder: {}", client_exe, path.display());
                 return Ok(path);
             }
         }
     }
 
+    // Fall back to folders that start with "tes3mp" (case insensitive)
+    for folder_name in &found_folders {
+        if folder_name.to_lowercase().starts_with("tes3mp") {
+            let path = extract_path.join(folder_name);
+            log::info!("Found matching TES3MP folder: {}", path.display());
+            return Ok(path);
+        }
+    }
+
     log::error!(
         "No TES3MP folder found. Available folders: {:?}",
         found_folders
@@ -54,10 +79,16 @@ pub fn find_tes3mp_folder(extract_path: &Path) -> Result<std::path::PathBuf, Str
 }
 
 pub fn get_appdata_dir() -> Result<std::path::PathBuf, String> {
-    let appdata = std::env::var("APPDATA")
-        .map_err(|e| format!("Failed to get APPDATA environment variable: {}", e))?;
+    // %APPDATA% on Windows, the XDG data dir (~/.local/share) everywhere else
+    let appdata = match std::env::var("APPDATA") {
+        Ok(appdata) => std::path::PathBuf::from(appdata),
+        Err(e) => dirs::data_dir().ok_or(format!(
+            "Failed to get APPDATA environment variable or data directory: {}",
+            e
+        ))?,
+    };
 
-    let nerevar_dir = Path::new(&appdata).join("Nerevar");
+    let nerevar_dir = appdata.join("Nerevar");
 
     // Create the directory if it doesn't exist
     if !nerevar_dir.exists() {
@@ -68,3 +99,54 @@ pub fn get_appdata_dir() -> Result<std::path::PathBuf, String> {
 
     Ok(nerevar_dir)
 }
+
+pub fn copy_dir_recursive(source: &Path, target: &Path) -> Result<(), String> {
+    fs::create_dir_all(target)
+        .map_err(|e| format!("Failed to create directory {}: {}", target.display(), e))?;
+
+    let entries = fs::read_dir(source)
+        .map_err(|e| format!("Failed to read directory {}: {}", source.display(), e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let source_path = entry.path();
+        let target_path = target.join(entry.file_name());
+
+        // Links are recreated rather than followed: Linux releases link their
+        // libraries, and a followed link loop would never end
+        let file_type = entry
+            .file_type()
+            .map_err(|e| format!("Failed to read {}: {}", source_path.display(), e))?;
+        if file_type.is_symlink() {
+            copy_symlink(&source_path, &target_path)?;
+        } else if file_type.is_dir() {
+            copy_dir_recursive(&source_path, &target_path)?;
+        } else {
+            fs::copy(&source_path, &target_path).map_err(|e| {
+                format!(
+                    "Failed to copy {} to {}: {}",
+                    source_path.display(),
+                    target_path.display(),
+                    e
+                )
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> Result<(), String> {
+    let link_target = fs::read_link(source)
+        .map_err(|e| format!("Failed to read symlink {}: {}", source.display(), e))?;
+    std::os::unix::fs::symlink(&link_target, target)
+        .map_err(|e| format!("Failed to create symlink {}: {}", target.display(), e))
+}
+
+// Windows releases don't ship symlinks, and creating them needs extra privileges
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, _target: &Path) -> Result<(), String> {
+    log::warn!("Skipping symlink {}", source.display());
+    Ok(())
+}
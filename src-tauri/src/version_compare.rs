@@ -0,0 +1,72 @@
+use crate::types::VersionComparison;
+use semver::Version;
+use std::cmp::Ordering;
+
+/// Parses a release version as semver.
+///
+/// Accepts the forms the release endpoints and TES3MP tags use: an optional
+/// `v` or `tes3mp-` prefix, two or three numeric components (`0.8` is read as
+/// `0.8.0`) and an optional `-prerelease`/`+build` suffix. Returns None for
+/// anything else rather than guessing.
+pub fn parse_version(raw: &str) -> Option<Version> {
+    let trimmed = raw.trim();
+    let trimmed = trimmed
+        .get(..7)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("tes3mp-"))
+        .map(|_| &trimmed[7..])
+        .unwrap_or(trimmed);
+    let trimmed = trimmed
+        .strip_prefix('v')
+        .or_else(|| trimmed.strip_prefix('V'))
+        .unwrap_or(trimmed);
+
+    let core_end = trimmed.find(['-', '+']).unwrap_or(trimmed.len());
+    let (core, suffix) = trimmed.split_at(core_end);
+
+    let parts: Vec<&str> = core.split('.').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+
+    // Parse the numbers ourselves so leading zeros like `0.08` don't fail
+    let mut numbers = [0u64; 3];
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        numbers[i] = part.parse().ok()?;
+    }
+
+    Version::parse(&format!(
+        "{}.{}.{}{}",
+        numbers[0], numbers[1], numbers[2], suffix
+    ))
+    .ok()
+}
+
+/// How `latest` relates to `current`. Build metadata is ignored, as semver
+/// precedence requires, and an unparseable side makes the result Unknown.
+pub fn compare_versions(current: &str, latest: &str) -> VersionComparison {
+    let (Some(current), Some(latest)) = (parse_version(current), parse_version(latest)) else {
+        return VersionComparison::Unknown;
+    };
+
+    match latest.cmp_precedence(&current) {
+        Ordering::Greater => VersionComparison::Newer,
+        Ordering::Less => VersionComparison::Older,
+        Ordering::Equal => VersionComparison::Equal,
+    }
+}
+
+/// Orders versions oldest first by semver precedence. Unparseable versions
+/// sort after all others, by name, so sorting a list never fails.
+pub fn version_order(a: &str, b: &str) -> Ordering {
+    match (parse_version(a), parse_version(b)) {
+        (Some(a_version), Some(b_version)) => {
+            a_version.cmp_precedence(&b_version).then_with(|| a.cmp(b))
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
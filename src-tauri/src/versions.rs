@@ -0,0 +1,300 @@
+use crate::config::{get_nerevar_config, save_nerevar_config};
+use crate::types::{InstallMetadata, InstalledVersion, NerevarConfig, Platform};
+use crate::utils::get_appdata_dir;
+use crate::version_compare::version_order;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory holding one folder per installed TES3MP version, each with a
+/// `<version>.json` metadata file next to it.
+pub fn versions_dir() -> Result<PathBuf, String> {
+    let versions_dir = get_appdata_dir()?.join("versions");
+
+    if !versions_dir.exists() {
+        fs::create_dir_all(&versions_dir)
+            .map_err(|e| format!("Failed to create versions directory: {}", e))?;
+        log::info!("Created versions directory: {}", versions_dir.display());
+    }
+
+    Ok(versions_dir)
+}
+
+pub fn version_install_dir(version: &str) -> Result<PathBuf, String> {
+    validate_version_name(version)?;
+    Ok(versions_dir()?.join(version))
+}
+
+fn metadata_path(version: &str) -> Result<PathBuf, String> {
+    validate_version_name(version)?;
+    Ok(versions_dir()?.join(format!("{}.json", version)))
+}
+
+// Versions become directory names, so keep them to a single safe path component
+pub fn validate_version_name(version: &str) -> Result<(), String> {
+    if version.is_empty() || version == "." || version == ".." || version.contains(['/', '\\', ':'])
+    {
+        return Err(format!("Invalid TES3MP version name: '{}'", version));
+    }
+    Ok(())
+}
+
+pub fn write_install_metadata(metadata: &InstallMetadata) -> Result<(), String> {
+    let path = metadata_path(&metadata.version)?;
+    let json = serde_json::to_string_pretty(metadata)
+        .map_err(|e| format!("Failed to serialize install metadata: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write install metadata: {}", e))?;
+
+    log::info!("Install metadata written: {}", path.display());
+    Ok(())
+}
+
+pub fn read_install_metadata(version: &str) -> Result<Option<InstallMetadata>, String> {
+    let path = metadata_path(version)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read install metadata: {}", e))?;
+    let metadata = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse install metadata: {}", e))?;
+    Ok(Some(metadata))
+}
+
+pub fn remove_install_metadata(version: &str) -> Result<(), String> {
+    let path = metadata_path(version)?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove install metadata: {}", e))?;
+    }
+    Ok(())
+}
+
+pub fn list_installed_versions() -> Result<Vec<InstalledVersion>, String> {
+    let config = get_nerevar_config()?;
+    let versions_dir = versions_dir()?;
+
+    let entries = fs::read_dir(&versions_dir)
+        .map_err(|e| format!("Failed to read versions directory: {}", e))?;
+
+    let mut versions = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let version = entry.file_name().to_string_lossy().to_string();
+        // Staging and backup folders from in-flight installs start with a dot
+        if version.starts_with('.') {
+            continue;
+        }
+
+        let metadata = match read_install_metadata(&version)? {
+            Some(metadata) => metadata,
+            None => InstallMetadata {
+                version: version.clone(),
+                tes3mp_path: path.to_string_lossy().to_string(),
+                platform: None,
+                installed_at: None,
+                source: None,
+            },
+        };
+
+        let active = config
+            .as_ref()
+            .is_some_and(|c| Path::new(&c.tes3mp_path) == path);
+        let mut pinned_servers: Vec<String> = config
+            .as_ref()
+            .map(|c| {
+                c.pinned_versions
+                    .iter()
+                    .filter(|(_, pinned)| **pinned == version)
+                    .map(|(server, _)| server.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        pinned_servers.sort();
+
+        versions.push(InstalledVersion {
+            version: metadata.version,
+            tes3mp_path: metadata.tes3mp_path,
+            platform: metadata.platform,
+            installed_at: metadata.installed_at,
+            source: metadata.source,
+            active,
+            pinned_servers,
+        });
+    }
+
+    versions.sort_by(|a, b| version_order(&a.version, &b.version));
+    Ok(versions)
+}
+
+fn require_config() -> Result<NerevarConfig, String> {
+    get_nerevar_config()?.ok_or("No Nerevar config found. Please install TES3MP first.".to_string())
+}
+
+fn require_installed(version: &str) -> Result<PathBuf, String> {
+    let install_dir = version_install_dir(version)?;
+    if !install_dir.is_dir() {
+        return Err(format!("TES3MP {} is not installed", version));
+    }
+    Ok(install_dir)
+}
+
+/// Makes `version` the install used by the launchers and config editors.
+pub fn activate_version(version: &str) -> Result<NerevarConfig, String> {
+    let install_dir = require_installed(version)?;
+    let mut config = require_config()?;
+
+    if config.version != version && !config.version.is_empty() {
+        config.previous_version = Some(config.version.clone());
+    }
+
+    config.tes3mp_path = install_dir.to_string_lossy().to_string();
+    config.version = version.to_string();
+    config.last_updated = chrono::Utc::now().to_rfc3339();
+    if let Some(metadata) = read_install_metadata(version)? {
+        config.platform = metadata.platform;
+    }
+    save_nerevar_config(&config)?;
+
+    log::info!("Activated TES3MP {}", version);
+    Ok(config)
+}
+
+/// Switches back to the version that was active before the last activation or install.
+pub fn rollback_version() -> Result<NerevarConfig, String> {
+    let config = require_config()?;
+    let previous = config
+        .previous_version
+        .ok_or("There is no previous TES3MP version to roll back to")?;
+
+    log::info!("Rolling back to TES3MP {}", previous);
+    activate_version(&previous)
+}
+
+/// Pins `server` (as `ip:port`) to `version`, or removes its pin when `version` is None.
+pub fn pin_version(server: &str, version: Option<&str>) -> Result<NerevarConfig, String> {
+    let mut config = require_config()?;
+
+    match version {
+        Some(version) => {
+            require_installed(version)?;
+            config
+                .pinned_versions
+                .insert(server.to_string(), version.to_string());
+            log::info!("Pinned {} to TES3MP {}", server, version);
+        }
+        None => {
+            config.pinned_versions.remove(server);
+            log::info!("Unpinned {}", server);
+        }
+    }
+
+    save_nerevar_config(&config)?;
+    Ok(config)
+}
+
+pub fn remove_version(version: &str) -> Result<(), String> {
+    let install_dir = require_installed(version)?;
+    let config = require_config()?;
+
+    if config.version == version {
+        return Err(format!(
+            "TES3MP {} is the active version. Activate another version first.",
+            version
+        ));
+    }
+
+    let pinned_by: Vec<&String> = config
+        .pinned_versions
+        .iter()
+        .filter(|(_, pinned)| *pinned == version)
+        .map(|(server, _)| server)
+        .collect();
+    if !pinned_by.is_empty() {
+        return Err(format!(
+            "TES3MP {} is pinned by: {:?}. Unpin it first.",
+            version, pinned_by
+        ));
+    }
+
+    fs::remove_dir_all(&install_dir)
+        .map_err(|e| format!("Failed to remove TES3MP {}: {}", version, e))?;
+
+    remove_install_metadata(version)?;
+    crate::manifest::remove_manifest(version)?;
+
+    log::info!("Removed TES3MP {}: {}", version, install_dir.display());
+    Ok(())
+}
+
+/// Install directory of the active TES3MP version.
+pub fn active_install_dir() -> Result<PathBuf, String> {
+    resolve_install_dir(None)
+}
+
+/// Install directory to use for `server`: its pinned version if it has one,
+/// otherwise the active version.
+pub fn resolve_install_dir(server: Option<&str>) -> Result<PathBuf, String> {
+    let config = require_config()?;
+
+    if let Some(version) = server.and_then(|s| config.pinned_versions.get(s)) {
+        log::info!(
+            "Using TES3MP {} pinned for {}",
+            version,
+            server.unwrap_or("")
+        );
+        return require_installed(version);
+    }
+
+    Ok(PathBuf::from(config.tes3mp_path))
+}
+
+/// Moves a pre-versioning install from `<appdata>/TES3MP` into the versions
+/// directory so it shows up alongside newer installs.
+pub fn migrate_legacy_install() -> Result<(), String> {
+    let Some(mut config) = get_nerevar_config()? else {
+        return Ok(());
+    };
+
+    let legacy_dir = get_appdata_dir()?.join("TES3MP");
+    if Path::new(&config.tes3mp_path) != legacy_dir || !legacy_dir.is_dir() {
+        return Ok(());
+    }
+
+    let version = if config.version.is_empty() {
+        "legacy".to_string()
+    } else {
+        config.version.clone()
+    };
+    let install_dir = version_install_dir(&version)?;
+    if install_dir.exists() {
+        return Err(format!(
+            "Cannot migrate legacy TES3MP install, {} already exists",
+            install_dir.display()
+        ));
+    }
+
+    log::info!(
+        "Migrating legacy TES3MP install from {} to {}",
+        legacy_dir.display(),
+        install_dir.display()
+    );
+    fs::rename(&legacy_dir, &install_dir)
+        .map_err(|e| format!("Failed to migrate legacy TES3MP install: {}", e))?;
+
+    write_install_metadata(&InstallMetadata {
+        version: version.clone(),
+        tes3mp_path: install_dir.to_string_lossy().to_string(),
+        platform: Some(config.platform.unwrap_or(Platform::Windows)),
+        installed_at: Some(config.last_updated.clone()),
+        source: None,
+    })?;
+
+    config.tes3mp_path = install_dir.to_string_lossy().to_string();
+    config.version = version;
+    save_nerevar_config(&config)
+}
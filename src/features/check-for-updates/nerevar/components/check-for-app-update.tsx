@@ -35,6 +35,7 @@ export function CheckForAppUpdate({ className }: { className?: string }) {
     update_available: boolean;
     version: string;
     url?: string;
+    comparison?: "newer" | "older" | "equal" | "unknown";
   }> => {
     try {
       const result = await invoke("check_for_app_update");
@@ -42,6 +43,7 @@ export function CheckForAppUpdate({ className }: { className?: string }) {
         update_available: boolean;
         version: string;
         url?: string;
+        comparison?: "newer" | "older" | "equal" | "unknown";
       };
     } catch (error) {
       console.log("Failed to check for app update:", error);
@@ -87,12 +89,12 @@ export function CheckForAppUpdate({ className }: { className?: string }) {
     try {
       // Step 1: Download the update
       setUpdateProgress(25);
-      const tempFilePath = await invoke("download_app_update", { downloadUrl });
+      await invoke("download_app_update", { downloadUrl });
 
-      // Step 2: Apply the update (this will restart the app automatically)
+      // Step 2: Apply the verified update (this will restart the app automatically)
       setUpdateProgress(50);
-      setUpdateStage("Applying update...");
-      await invoke("apply_app_update", { tempFilePath });
+      setUpdateStage("Verifying and applying update...");
+      await invoke("apply_app_update");
 
       setUpdateProgress(100);
       setUpdateStage("Update complete! Restarting...");
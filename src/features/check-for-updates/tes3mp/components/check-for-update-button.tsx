@@ -5,10 +5,23 @@ import { invoke } from "@tauri-apps/api/core";
 import { Download, Loader2, RotateCwIcon } from "lucide-react";
 import { useState } from "react";
 import { toast } from "sonner";
+import { describeInstallError } from "@/types/install-error";
 
 type UpdateCheckResponse = {
   update_available: boolean;
   version: string;
+  current_version: string;
+  comparison: "newer" | "older" | "equal" | "unknown";
+  release_notes?: string;
+  release_date?: string;
+  download_size?: number;
+  breaking: boolean;
+  changelog: {
+    version: string;
+    release_date?: string;
+    notes: string;
+    breaking: boolean;
+  }[];
 };
 
 export function CheckForTes3MpUpdateButton({
@@ -28,11 +41,25 @@ export function CheckForTes3MpUpdateButton({
       );
       console.log("TES3MP update result:", result);
       if (result.update_available) {
-        toast.info(`Tes3MP ${result.version} is available`);
+        if (result.breaking) {
+          toast.warning(
+            `Tes3MP ${result.version} is available and includes breaking changes, check the release notes before updating`
+          );
+        } else {
+          toast.info(`Tes3MP ${result.version} is available`);
+        }
         setUpdateAvailable(true);
         setUpdateVersion(result.version);
       } else {
-        toast.info(`Tes3MP is up to date!`);
+        if (result.comparison === "older") {
+          toast.info(
+            `Tes3MP ${result.current_version} is newer than the latest release (${result.version})`
+          );
+        } else if (result.comparison === "unknown") {
+          toast.warning("Could not compare Tes3MP versions");
+        } else {
+          toast.info(`Tes3MP is up to date!`);
+        }
         setUpdateAvailable(false);
         setUpdateVersion("");
       }
@@ -45,10 +72,11 @@ export function CheckForTes3MpUpdateButton({
   };
   const handleDownloadUpdate = async () => {
     try {
-      toast.promise(invoke("download_latest_windows_release"), {
+      toast.promise(invoke("upgrade_tes3mp"), {
         loading: "Downloading update...",
         success: "Update downloaded successfully!",
-        error: "Error downloading update",
+        error: (error) =>
+          `Error downloading update: ${describeInstallError(error)}`,
       });
       setUpdateAvailable(false);
       setUpdateVersion("");
@@ -30,7 +30,7 @@ export function ManualConnectCard({ className }: { className?: string }) {
       if (!result) {
         throw new Error("Failed to update client config to connect to server");
       }
-      await invoke("run_tes3mp");
+      await invoke("run_tes3mp", { server: `${ip}:${parseInt(port)}` });
     } catch (error) {
       console.error("Failed to connect to server:", error);
     }
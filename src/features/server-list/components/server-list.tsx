@@ -636,7 +636,7 @@ function ConnectDialog({
       if (success) {
         console.log("TES3MP client config updated successfully");
         // Here you could also launch TES3MP automatically
-        await invoke("run_tes3mp");
+        await invoke("run_tes3mp", { server: `${ip}:${parseInt(port)}` });
       } else {
         throw new Error("Failed to update TES3MP client config");
       }
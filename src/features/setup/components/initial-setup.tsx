@@ -9,6 +9,12 @@ import Image from "next/image";
 import { useOpenMWConfig } from "@/features/app-config/context/openmw-config-context";
 import { useOpenMWWizard } from "@/hooks/use-openmw-wizard";
 import { CheckCircle } from "lucide-react";
+import {
+  describeInstallError,
+  type InstallError,
+} from "@/types/install-error";
+
+const INSTALL_OPERATION_ID = "initial-setup-install";
 
 export function InitialSetup({ onFinish }: { onFinish: () => void }) {
   const [isDownloading, setIsDownloading] = useState(false);
@@ -19,25 +25,41 @@ export function InitialSetup({ onFinish }: { onFinish: () => void }) {
   const handleCTAClick = async () => {
     setIsDownloading(true);
 
-    toast.promise(invoke("download_latest_windows_release"), {
-      loading: "Installing TES3MP...",
-      success: async (data) => {
-        if (!data) {
-          throw new Error("Failed to install TES3MP");
-        }
-        await refreshConfig();
+    toast.promise(
+      invoke("download_latest_windows_release", {
+        operationId: INSTALL_OPERATION_ID,
+      }),
+      {
+        loading: "Installing TES3MP...",
+        success: async (data) => {
+          if (!data) {
+            throw new Error("Failed to install TES3MP");
+          }
+          await refreshConfig();
+
+          setIsDownloading(false);
+          setTes3mpInstalled(true);
+          return "TES3MP installed successfully";
+        },
+        error: (error) => {
+          console.error("Tes3MP installation error:", error);
+          setIsDownloading(false);
+          setTes3mpInstalled(false);
+          if ((error as InstallError)?.kind === "cancelled") {
+            return "TES3MP installation cancelled";
+          }
+          return `TES3MP installation error: ${describeInstallError(error)}`;
+        },
+      }
+    );
+  };
 
-        setIsDownloading(false);
-        setTes3mpInstalled(true);
-        return "TES3MP installed successfully";
-      },
-      error: (error) => {
-        console.error("Tes3MP installation error:", error);
-        setIsDownloading(false);
-        setTes3mpInstalled(false);
-        return "TES3MP installation error";
-      },
-    });
+  const handleCancelClick = async () => {
+    try {
+      await invoke("cancel_operation", { operationId: INSTALL_OPERATION_ID });
+    } catch (error) {
+      console.error("Failed to cancel TES3MP installation:", error);
+    }
   };
 
   const handleOpenMWWizardClick = async () => {
@@ -110,6 +132,19 @@ export function InitialSetup({ onFinish }: { onFinish: () => void }) {
               </span>
             </motion.button>
           )}
+          {isDownloading && (
+            <motion.button
+              key="cancel-install-tes3mp"
+              className="relative px-6 py-2 text-white/70 hover:text-white border border-white/20 rounded-lg cursor-pointer transition-colors duration-300"
+              initial={{ opacity: 0 }}
+              animate={{ opacity: 1 }}
+              exit={{ opacity: 0 }}
+              transition={{ duration: 0.3 }}
+              onClick={handleCancelClick}
+            >
+              {`Cancel`}
+            </motion.button>
+          )}
           <div className="flex flex-col gap-4">
             {tes3mpInstalled && !isLoading && !isDownloading && (
               <>
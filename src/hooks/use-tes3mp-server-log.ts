@@ -0,0 +1,47 @@
+"use client";
+
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/core";
+import { ServerLogLine } from "@/types/process";
+
+// Matches the number of lines the backend keeps in memory
+const MAX_LINES = 5000;
+
+export const useTES3MPServerLog = () => {
+  const [lines, setLines] = useState<ServerLogLine[]>([]);
+
+  useEffect(() => {
+    // Load what the server printed before this component mounted
+    invoke<ServerLogLine[]>("get_server_log")
+      .then((buffered) => setLines(buffered))
+      .catch((error) => console.error("Failed to get server log:", error));
+
+    const unlistenLog = listen<ServerLogLine>("tes3mp-server-log", (event) => {
+      setLines((previous) => [...previous, event.payload].slice(-MAX_LINES));
+    });
+
+    return () => {
+      unlistenLog.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  const clear = async () => {
+    await invoke("clear_server_log");
+    setLines([]);
+  };
+
+  // Sent commands are echoed back as "stdin" lines through the log event
+  const sendCommand = async (command: string) => {
+    await invoke("send_server_command", { command });
+  };
+
+  const getHistory = () => invoke<string[]>("get_server_command_history");
+
+  return {
+    lines,
+    clear,
+    sendCommand,
+    getHistory,
+  };
+};
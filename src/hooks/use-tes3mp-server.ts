@@ -2,12 +2,16 @@
 
 import { useEffect, useState } from "react";
 import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/core";
+import { ProcessInfo } from "@/types/process";
 
 export interface TES3MPServerEvent {
   pid: number;
   success: boolean;
   exit_code: number | null;
   message: string;
+  // Stopped or killed from Nerevar rather than exiting on its own
+  requested: boolean;
 }
 
 export const useTES3MPServer = () => {
@@ -16,6 +20,17 @@ export const useTES3MPServer = () => {
   const [lastResult, setLastResult] = useState<TES3MPServerEvent | null>(null);
 
   useEffect(() => {
+    // Pick up a process started before this component mounted
+    invoke<ProcessInfo[]>("list_processes")
+      .then((processes) => {
+        const running = processes.find((p) => p.role === "server");
+        if (running) {
+          setIsRunning(true);
+          setServerPid(running.pid);
+        }
+      })
+      .catch((error) => console.error("Failed to list processes:", error));
+
     // Listen for TES3MP started event
     const unlistenStarted = listen<number>("tes3mp-server-started", (event) => {
       console.log("TES3MP started with PID:", event.payload);
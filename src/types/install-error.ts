@@ -0,0 +1,41 @@
+export type InstallError =
+  | {
+      kind: "insufficient_space";
+      path: string;
+      required: number;
+      available: number;
+    }
+  | { kind: "not_writable"; path: string; reason: string }
+  | {
+      kind: "downgrade_required";
+      installed: string;
+      available: string;
+      channel: "stable" | "beta" | "nightly";
+    }
+  | { kind: "checksum_unavailable"; version: string; url: string }
+  | { kind: "cancelled" }
+  | { kind: "failed"; message: string };
+
+const toMB = (bytes: number) => Math.ceil(bytes / (1024 * 1024));
+
+export function describeInstallError(error: unknown): string {
+  const installError = error as InstallError | undefined;
+  switch (installError?.kind) {
+    case "insufficient_space":
+      return `Not enough disk space on ${installError.path}: ${toMB(
+        installError.required
+      )} MB needed, ${toMB(installError.available)} MB free`;
+    case "not_writable":
+      return `Cannot write to ${installError.path}: ${installError.reason}`;
+    case "downgrade_required":
+      return `The latest ${installError.channel} release (${installError.available}) is older than the installed version (${installError.installed})`;
+    case "checksum_unavailable":
+      return `No checksum is known for TES3MP ${installError.version}, so it can't be installed from ${installError.url}. Try again once the Nerevar API is reachable.`;
+    case "cancelled":
+      return "Cancelled";
+    case "failed":
+      return installError.message;
+    default:
+      return String(error);
+  }
+}
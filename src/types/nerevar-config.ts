@@ -1,6 +1,27 @@
+import { WatchdogPolicy } from "@/types/process";
+import { ScheduledTask } from "@/types/schedule";
+
 export type NerevarConfig = {
   tes3mp_path: string;
   version: string;
   last_updated: string;
   mode?: "player" | "server";
+  platform?: "windows" | "linux";
+  previous_version?: string;
+  pinned_versions?: Record<string, string>;
+  api_url?: string;
+  use_local_api?: boolean;
+  channel?: "stable" | "beta" | "nightly";
+  watchdog?: WatchdogPolicy;
+  schedules?: ScheduledTask[];
+};
+
+export type InstalledVersion = {
+  version: string;
+  tes3mp_path: string;
+  platform?: "windows" | "linux";
+  installed_at?: string;
+  source?: string;
+  active: boolean;
+  pinned_servers: string[];
 };
@@ -0,0 +1,61 @@
+export type ProcessRole =
+  | "wizard"
+  | "launcher"
+  | "browser"
+  | "client"
+  | "server";
+
+export type ProcessInfo = {
+  pid: number;
+  role: ProcessRole;
+  executable: string;
+  started_at: string;
+};
+
+// Payload of the `process-stats` event and `get_process_stats`
+export type ProcessStats = {
+  pid: number;
+  role: ProcessRole;
+  cpu_percent: number;
+  memory_bytes: number;
+  threads: number | null;
+  uptime_seconds: number;
+  sampled_at: string;
+};
+
+export type ServerLogLine = {
+  pid: number;
+  stream: "stdout" | "stderr" | "stdin";
+  line: string;
+  timestamp: string;
+};
+
+export type WatchdogPolicy = {
+  enabled: boolean;
+  max_restarts: number;
+  window_seconds: number;
+  initial_backoff_seconds: number;
+  max_backoff_seconds: number;
+};
+
+export type CrashReport = {
+  pid: number;
+  exit_code: number | null;
+  crashed_at: string;
+  log_tail: string[];
+};
+
+// Payload of `tes3mp-server-restarting`
+export type ServerRestart = {
+  attempt: number;
+  delay_seconds: number;
+  crash: CrashReport;
+};
+
+// Returned by `get_watchdog_status` and sent with `tes3mp-server-crash-loop`
+export type WatchdogStatus = {
+  policy: WatchdogPolicy;
+  crash_loop: boolean;
+  restart_pending: boolean;
+  recent_crashes: CrashReport[];
+};
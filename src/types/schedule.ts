@@ -0,0 +1,38 @@
+export type ScheduledAction =
+  | "restart_server"
+  | "backup_world"
+  | "check_for_updates";
+
+export type ScheduledTask = {
+  id: string;
+  // Cron expression in local time, e.g. "0 4 * * *" for 4am every day
+  cron: string;
+  action: ScheduledAction;
+  enabled?: boolean;
+  // Minutes before each run to emit `scheduled-action-pending`
+  warn_minutes?: number[];
+};
+
+// Payload of `scheduled-action-pending`
+export type ScheduledActionNotice = {
+  task_id: string;
+  action: ScheduledAction;
+  run_at: string;
+  minutes_left: number;
+};
+
+// Payload of `scheduled-action-finished`
+export type ScheduledRun = {
+  task_id: string;
+  action: ScheduledAction;
+  ran_at: string;
+  success: boolean;
+  message: string;
+};
+
+// Returned by `get_schedules`
+export type ScheduleStatus = {
+  task: ScheduledTask;
+  next_run: string | null;
+  last_run: ScheduledRun | null;
+};